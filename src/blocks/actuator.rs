@@ -0,0 +1,234 @@
+use crossterm::event::KeyEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::Editing;
+use crate::utils::{Field, FieldKind, FieldList, NumericInput};
+
+/// Actuator stage between the commanded input and the input actually applied to the plant.
+///
+/// The command goes through a deadband of half width `deadband`, which removes small commands
+/// and shifts the larger ones so that the characteristic stays continuous, then the saturation
+/// [u_min, u_max], the rate limit (none when zero) and the first order lag 1 / (tau s + 1)
+/// discretized with a zero order hold. The output of the lag drives the plant through a
+/// mechanical play of width `backlash`, which only follows once the play has been taken up.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct Actuator {
+    enabled: bool,
+    u_min: f64,    // lower saturation limit
+    u_max: f64,    // upper saturation limit
+    rate: f64,     // largest change per second, no limit when zero
+    tau: f64,      // lag time constant
+    deadband: f64, // half width of the deadband
+    backlash: f64, // width of the play
+    Ts: f64,       // sampling time
+    command: f64,  // last commanded input
+    limited: f64,  // rate limited command
+    lagged: f64,   // output of the lag
+    applied: f64,  // input applied to the plant
+    edit: Option<(ActuatorField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ActuatorField {
+    Mode,
+    Min,
+    Max,
+    Rate,
+    Tau,
+    Deadband,
+    Backlash,
+}
+
+impl Field for ActuatorField {
+    fn label(self) -> &'static str {
+        match self {
+            ActuatorField::Mode => "mode",
+            ActuatorField::Min => "u_min",
+            ActuatorField::Max => "u_max",
+            ActuatorField::Rate => "rate",
+            ActuatorField::Tau => "tau",
+            ActuatorField::Deadband => "deadband",
+            ActuatorField::Backlash => "backlash",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            ActuatorField::Mode => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+impl Default for Actuator {
+    fn default() -> Self {
+        Self::new(0.1)
+    }
+}
+
+impl Actuator {
+    #[allow(non_snake_case)]
+    pub fn new(Ts: f64) -> Self {
+        Self {
+            enabled: false,
+            u_min: -10.0,
+            u_max: 10.0,
+            rate: 20.0,
+            tau: 0.2,
+            deadband: 0.0,
+            backlash: 0.0,
+            Ts,
+            command: 0.0,
+            limited: 0.0,
+            lagged: 0.0,
+            applied: 0.0,
+            edit: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Computes the input applied to the plant for the commanded input `u`.
+    pub fn step(&mut self, u: f64) -> f64 {
+        self.command = u;
+        if !self.enabled {
+            self.limited = u;
+            self.lagged = u;
+            self.applied = u;
+            return u;
+        }
+        let u = if u.abs() <= self.deadband {
+            0.0
+        } else {
+            u - self.deadband * u.signum()
+        };
+        let u = u.clamp(self.u_min, self.u_max);
+        self.limited = if self.rate > 0.0 {
+            let max_step = self.rate * self.Ts;
+            self.limited + (u - self.limited).clamp(-max_step, max_step)
+        } else {
+            u
+        };
+        let a = if self.tau > 0.0 {
+            (-self.Ts / self.tau).exp()
+        } else {
+            0.0
+        };
+        self.lagged = a * self.lagged + (1.0 - a) * self.limited;
+        let play = self.backlash / 2.0;
+        self.applied = self.applied.clamp(self.lagged - play, self.lagged + play);
+        self.applied
+    }
+
+    pub fn reset(&mut self) {
+        self.command = 0.0;
+        self.limited = 0.0;
+        self.lagged = 0.0;
+        self.applied = 0.0;
+    }
+
+    fn value(&self, field: ActuatorField) -> f64 {
+        match field {
+            ActuatorField::Mode => 0.0,
+            ActuatorField::Min => self.u_min,
+            ActuatorField::Max => self.u_max,
+            ActuatorField::Rate => self.rate,
+            ActuatorField::Tau => self.tau,
+            ActuatorField::Deadband => self.deadband,
+            ActuatorField::Backlash => self.backlash,
+        }
+    }
+
+    pub fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(1)
+    }
+
+    pub fn edit(&mut self, editing: &mut Editing, k: KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    pub fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+}
+
+impl FieldList for Actuator {
+    type Field = ActuatorField;
+
+    fn fields(&self) -> Vec<ActuatorField> {
+        let mut fields = vec![ActuatorField::Mode];
+        if self.enabled {
+            fields.extend([
+                ActuatorField::Min,
+                ActuatorField::Max,
+                ActuatorField::Rate,
+                ActuatorField::Tau,
+                ActuatorField::Deadband,
+                ActuatorField::Backlash,
+            ]);
+        }
+        fields
+    }
+
+    fn text(&self, field: ActuatorField) -> String {
+        match field {
+            ActuatorField::Mode => if self.enabled { "on" } else { "off" }.to_string(),
+            _ => self.value(field).to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: ActuatorField, value: f64) {
+        match field {
+            ActuatorField::Mode => {}
+            // the limits must stay ordered for the saturation
+            ActuatorField::Min => self.u_min = value.min(self.u_max),
+            ActuatorField::Max => self.u_max = value.max(self.u_min),
+            ActuatorField::Rate => self.rate = value.max(0.0),
+            ActuatorField::Tau => self.tau = value.max(0.0),
+            ActuatorField::Deadband => self.deadband = value.max(0.0),
+            ActuatorField::Backlash => self.backlash = value.max(0.0),
+        }
+    }
+
+    fn cycle(&mut self, _field: ActuatorField, _forward: bool) {
+        self.enabled = !self.enabled;
+        // start from the last command instead of ramping from zero
+        self.limited = self.command;
+        self.lagged = self.command;
+        self.applied = self.command;
+    }
+
+    fn field_edit(&self) -> Option<&(ActuatorField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(ActuatorField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl StatefulWidgetRef for Actuator {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = self.field_lines();
+        if self.is_enabled() {
+            lines.push(Line::from(Span::styled(
+                format!("u = {:.2} applied = {:.2}", self.command, self.applied),
+                Style::default().gray().add_modifier(Modifier::BOLD),
+            )));
+        }
+        self.panel(lines).render(area, buf);
+    }
+}
@@ -0,0 +1,276 @@
+use crossterm::event::KeyEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, Paragraph, StatefulWidgetRef, Widget};
+
+use crate::Editing;
+use crate::inputs::Reference;
+use crate::utils::{Field, FieldKind, FieldList, NumericInput};
+
+/// Point of the loop where a disturbance enters.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DisturbanceChannel {
+    /// added to the input of the actuated plant, after the actuator
+    Load,
+    /// added to the output of the plant, before the sensor
+    Output,
+}
+
+impl DisturbanceChannel {
+    fn label(self) -> &'static str {
+        match self {
+            DisturbanceChannel::Load => "load",
+            DisturbanceChannel::Output => "output",
+        }
+    }
+}
+
+/// Disturbance signal, the sum of a generator taken from the reference registry and of the
+/// steps and impulses injected by hand during the run.
+pub struct Disturbance {
+    generator: Option<Box<dyn Reference + Send>>,
+    offset: f64,    // sum of the injected steps
+    pulse: f64,     // injected impulse, applied for one sample
+    injected: bool, // something was injected since the last reset
+    value: f64,
+}
+
+impl Default for Disturbance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Disturbance {
+    pub fn new() -> Self {
+        Self {
+            generator: None,
+            offset: 0.0,
+            pulse: 0.0,
+            injected: false,
+            value: 0.0,
+        }
+    }
+
+    /// True when the disturbance can be different from zero.
+    pub fn is_active(&self) -> bool {
+        self.generator.is_some() || self.injected
+    }
+
+    pub fn generator_name(&self) -> Option<&'static str> {
+        self.generator.as_ref().map(|g| g.name())
+    }
+
+    pub fn set_generator(&mut self, generator: Option<Box<dyn Reference + Send>>) {
+        self.generator = generator;
+    }
+
+    /// Adds a step of height `amplitude` from the next sample on.
+    pub fn inject_step(&mut self, amplitude: f64) {
+        self.offset += amplitude;
+        self.injected = true;
+    }
+
+    /// Adds a pulse of height `amplitude` lasting one sample.
+    pub fn inject_impulse(&mut self, amplitude: f64) {
+        self.pulse += amplitude;
+        self.injected = true;
+    }
+
+    /// Computes the disturbance of the next sample.
+    pub fn step(&mut self) -> f64 {
+        let generated = self
+            .generator
+            .as_mut()
+            .and_then(|g| g.next())
+            .map_or(0.0, |(_, y)| y);
+        self.value = generated + self.offset + std::mem::take(&mut self.pulse);
+        self.value
+    }
+
+    pub fn reset(&mut self) {
+        if let Some(generator) = self.generator.as_mut() {
+            generator.reset();
+        }
+        self.offset = 0.0;
+        self.pulse = 0.0;
+        self.injected = false;
+        self.value = 0.0;
+    }
+
+    pub fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.generator
+            .as_ref()
+            .map_or((0, 0), |g| g.get_cursor_offsets())
+    }
+
+    /// Edits the generator, which only knows the editing state of the reference.
+    pub fn edit(&mut self, editing: &mut Editing, k: KeyEvent) {
+        let Some(generator) = self.generator.as_mut() else {
+            *editing = Editing::None;
+            return;
+        };
+        let mut state = Editing::Reference;
+        generator.edit(&mut state, k);
+        if let Editing::None = state {
+            *editing = Editing::None;
+        }
+    }
+
+    pub fn set_edit(&mut self) {
+        if let Some(generator) = self.generator.as_mut() {
+            generator.set_edit();
+        }
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, editing: bool) {
+        match self.generator.as_ref() {
+            Some(generator) => {
+                let mut state = if editing {
+                    Editing::Reference
+                } else {
+                    Editing::None
+                };
+                generator.render(frame, area, &mut state);
+            }
+            None => frame.render_widget(
+                Paragraph::new(Line::from(Span::styled(
+                    format!("off, d = {:.2}", self.value),
+                    Style::default().gray().add_modifier(Modifier::BOLD),
+                ))),
+                area,
+            ),
+        }
+    }
+}
+
+/// Settings of the steps and impulses injected by hand into one of the disturbances.
+#[derive(Clone)]
+pub struct Injection {
+    amplitude: f64,
+    target: DisturbanceChannel,
+    edit: Option<(InjectionField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum InjectionField {
+    Amplitude,
+    Target,
+}
+
+const FIELDS: [InjectionField; 2] = [InjectionField::Amplitude, InjectionField::Target];
+
+impl Field for InjectionField {
+    fn label(self) -> &'static str {
+        match self {
+            InjectionField::Amplitude => "amplitude",
+            InjectionField::Target => "target",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            InjectionField::Amplitude => FieldKind::Number,
+            InjectionField::Target => FieldKind::Choice,
+        }
+    }
+}
+
+impl Default for Injection {
+    fn default() -> Self {
+        Self::new(5.0)
+    }
+}
+
+impl Injection {
+    pub fn new(amplitude: f64) -> Self {
+        Self {
+            amplitude,
+            target: DisturbanceChannel::Load,
+            edit: None,
+        }
+    }
+
+    pub fn amplitude(&self) -> f64 {
+        self.amplitude
+    }
+
+    pub fn target(&self) -> DisturbanceChannel {
+        self.target
+    }
+
+    pub fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(1)
+    }
+
+    pub fn edit(&mut self, editing: &mut Editing, k: KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    pub fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+}
+
+impl FieldList for Injection {
+    type Field = InjectionField;
+
+    fn fields(&self) -> Vec<InjectionField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: InjectionField) -> String {
+        match field {
+            InjectionField::Amplitude => self.amplitude.to_string(),
+            InjectionField::Target => self.target.label().to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: InjectionField, value: f64) {
+        if field == InjectionField::Amplitude {
+            self.amplitude = value;
+        }
+    }
+
+    fn cycle(&mut self, _field: InjectionField, _forward: bool) {
+        self.target = match self.target {
+            DisturbanceChannel::Load => DisturbanceChannel::Output,
+            DisturbanceChannel::Output => DisturbanceChannel::Load,
+        };
+    }
+
+    fn field_edit(&self) -> Option<&(InjectionField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(InjectionField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl StatefulWidgetRef for Injection {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = self.field_lines();
+        lines.push(Line::from(vec![
+            Span::styled(
+                "step ",
+                Style::default().gray().add_modifier(Modifier::BOLD),
+            ),
+            "<x>".blue().bold(),
+            Span::styled(
+                " impulse ",
+                Style::default().gray().add_modifier(Modifier::BOLD),
+            ),
+            "<X>".blue().bold(),
+        ]));
+        self.panel(lines).render(area, buf);
+    }
+}
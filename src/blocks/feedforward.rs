@@ -0,0 +1,300 @@
+use crossterm::event::KeyEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::Editing;
+use crate::utils::{Field, FieldKind, FieldList, LeadLag, NumericInput, cycle_index};
+
+/// Feedforward block whose output is added to the controller output before it enters the plant.
+///
+/// The filter is either a static gain, a lead-lag K (T_lead s + 1) / (T_lag s + 1), or the
+/// inverse of a first order plant model (tau_m s + 1) / (K_m (T_f s + 1)) made proper by the
+/// filter time constant T_f. It is driven either by the reference or by the measured load
+/// disturbance, which is subtracted to counteract it. Without a load disturbance to measure, the
+/// disturbance source contributes nothing and the panel says so.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct Feedforward {
+    mode: FeedforwardMode,
+    source: FeedforwardSource,
+    K: f64,      // static or lead-lag gain
+    T_lead: f64, // lead time constant
+    T_lag: f64,  // lag time constant
+    K_m: f64,    // inverted model gain
+    tau_m: f64,  // inverted model time constant
+    T_f: f64,    // inverse model filter time constant
+    Ts: f64,     // sampling time
+    filter: LeadLag,
+    u_ff: f64,                  // last feedforward output
+    disturbance_measured: bool, // a load disturbance was available at the last step
+    edit: Option<(FeedforwardField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FeedforwardMode {
+    Off,
+    StaticGain,
+    LeadLag,
+    InverseModel,
+}
+
+const MODES: [FeedforwardMode; 4] = [
+    FeedforwardMode::Off,
+    FeedforwardMode::StaticGain,
+    FeedforwardMode::LeadLag,
+    FeedforwardMode::InverseModel,
+];
+
+impl FeedforwardMode {
+    fn label(self) -> &'static str {
+        match self {
+            FeedforwardMode::Off => "off",
+            FeedforwardMode::StaticGain => "gain",
+            FeedforwardMode::LeadLag => "lead-lag",
+            FeedforwardMode::InverseModel => "inverse model",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FeedforwardSource {
+    Reference,
+    Disturbance,
+}
+
+impl FeedforwardSource {
+    fn label(self) -> &'static str {
+        match self {
+            FeedforwardSource::Reference => "reference",
+            FeedforwardSource::Disturbance => "disturbance",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FeedforwardField {
+    Mode,
+    Source,
+    Gain,
+    Lead,
+    Lag,
+    ModelGain,
+    ModelTau,
+    Filter,
+}
+
+impl Field for FeedforwardField {
+    fn label(self) -> &'static str {
+        match self {
+            FeedforwardField::Mode => "mode",
+            FeedforwardField::Source => "source",
+            FeedforwardField::Gain => "K",
+            FeedforwardField::Lead => "T_lead",
+            FeedforwardField::Lag => "T_lag",
+            FeedforwardField::ModelGain => "K_m",
+            FeedforwardField::ModelTau => "tau_m",
+            FeedforwardField::Filter => "T_f",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            FeedforwardField::Mode | FeedforwardField::Source => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+impl Default for Feedforward {
+    fn default() -> Self {
+        Self::new(0.1)
+    }
+}
+
+impl Feedforward {
+    #[allow(non_snake_case)]
+    pub fn new(Ts: f64) -> Self {
+        let mut ff = Self {
+            mode: FeedforwardMode::Off,
+            source: FeedforwardSource::Reference,
+            K: 1.0,
+            T_lead: 1.0,
+            T_lag: 0.5,
+            K_m: 1.0,
+            tau_m: 1.0,
+            T_f: 0.2,
+            Ts,
+            filter: LeadLag::default(),
+            u_ff: 0.0,
+            disturbance_measured: false,
+            edit: None,
+        };
+        ff.update_filter();
+        ff
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.mode != FeedforwardMode::Off
+    }
+
+    /// Computes the feedforward contribution for the current reference `r` and measured load
+    /// disturbance `d`, none when the loop has no load disturbance.
+    pub fn step(&mut self, r: f64, d: Option<f64>) -> f64 {
+        self.disturbance_measured = d.is_some();
+        self.u_ff = match (self.mode, self.source, d) {
+            (FeedforwardMode::Off, _, _) => 0.0,
+            (_, FeedforwardSource::Reference, _) => self.filter.step(r),
+            (_, FeedforwardSource::Disturbance, Some(d)) => -self.filter.step(d),
+            (_, FeedforwardSource::Disturbance, None) => 0.0,
+        };
+        self.u_ff
+    }
+
+    pub fn reset(&mut self) {
+        self.filter.reset();
+        self.u_ff = 0.0;
+    }
+
+    fn update_filter(&mut self) {
+        match self.mode {
+            FeedforwardMode::Off | FeedforwardMode::StaticGain => {
+                self.filter.retune(self.K, 0.0, 0.0, self.Ts)
+            }
+            FeedforwardMode::LeadLag => {
+                self.filter.retune(self.K, self.T_lead, self.T_lag, self.Ts)
+            }
+            FeedforwardMode::InverseModel => {
+                let k = if self.K_m != 0.0 { 1.0 / self.K_m } else { 0.0 };
+                self.filter.retune(k, self.tau_m, self.T_f, self.Ts)
+            }
+        }
+    }
+
+    fn value(&self, field: FeedforwardField) -> f64 {
+        match field {
+            FeedforwardField::Mode | FeedforwardField::Source => 0.0,
+            FeedforwardField::Gain => self.K,
+            FeedforwardField::Lead => self.T_lead,
+            FeedforwardField::Lag => self.T_lag,
+            FeedforwardField::ModelGain => self.K_m,
+            FeedforwardField::ModelTau => self.tau_m,
+            FeedforwardField::Filter => self.T_f,
+        }
+    }
+
+    pub fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(1)
+    }
+
+    pub fn edit(&mut self, editing: &mut Editing, k: KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    pub fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+}
+
+impl FieldList for Feedforward {
+    type Field = FeedforwardField;
+
+    fn fields(&self) -> Vec<FeedforwardField> {
+        let mut fields = vec![FeedforwardField::Mode];
+        match self.mode {
+            FeedforwardMode::Off => {}
+            FeedforwardMode::StaticGain => {
+                fields.extend([FeedforwardField::Source, FeedforwardField::Gain])
+            }
+            FeedforwardMode::LeadLag => fields.extend([
+                FeedforwardField::Source,
+                FeedforwardField::Gain,
+                FeedforwardField::Lead,
+                FeedforwardField::Lag,
+            ]),
+            FeedforwardMode::InverseModel => fields.extend([
+                FeedforwardField::Source,
+                FeedforwardField::ModelGain,
+                FeedforwardField::ModelTau,
+                FeedforwardField::Filter,
+            ]),
+        }
+        fields
+    }
+
+    fn text(&self, field: FeedforwardField) -> String {
+        match field {
+            FeedforwardField::Mode => self.mode.label().to_string(),
+            FeedforwardField::Source => self.source.label().to_string(),
+            _ => self.value(field).to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: FeedforwardField, value: f64) {
+        match field {
+            FeedforwardField::Mode | FeedforwardField::Source => {}
+            FeedforwardField::Gain => self.K = value,
+            FeedforwardField::Lead => self.T_lead = value.max(0.0),
+            FeedforwardField::Lag => self.T_lag = value.max(0.0),
+            FeedforwardField::ModelGain => self.K_m = value,
+            FeedforwardField::ModelTau => self.tau_m = value.max(0.0),
+            FeedforwardField::Filter => self.T_f = value.max(0.0),
+        }
+        self.update_filter();
+    }
+
+    fn cycle(&mut self, field: FeedforwardField, forward: bool) {
+        match field {
+            FeedforwardField::Mode => {
+                let idx = MODES.iter().position(|m| *m == self.mode).unwrap_or(0);
+                self.mode = MODES[cycle_index(idx, MODES.len(), forward)];
+                self.update_filter();
+            }
+            _ => {
+                self.source = match self.source {
+                    FeedforwardSource::Reference => FeedforwardSource::Disturbance,
+                    FeedforwardSource::Disturbance => FeedforwardSource::Reference,
+                };
+            }
+        }
+        self.filter.reset();
+    }
+
+    fn field_edit(&self) -> Option<&(FeedforwardField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(FeedforwardField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl StatefulWidgetRef for Feedforward {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = self.field_lines();
+        if self.is_enabled()
+            && self.source == FeedforwardSource::Disturbance
+            && !self.disturbance_measured
+        {
+            lines.push(Line::from(Span::styled(
+                "no load disturbance <l/L>",
+                Style::default().red().add_modifier(Modifier::BOLD),
+            )));
+        }
+        if self.is_enabled() {
+            lines.push(Line::from(Span::styled(
+                format!("u_ff = {:.2}", self.u_ff),
+                Style::default().gray().add_modifier(Modifier::BOLD),
+            )));
+        }
+        self.panel(lines).render(area, buf);
+    }
+}
@@ -0,0 +1,131 @@
+use crossterm::event::KeyEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::Editing;
+use crate::utils::{Field, FieldList, NumericInput};
+
+/// Divergence guard watching the signals of the loop.
+///
+/// Unstable loops grow without bound until the samples overflow to infinity and NaN, which the
+/// charts cannot draw. The guard trips as soon as a signal leaves [-limit, limit] or stops being
+/// finite, and remembers which one so that the simulation can be frozen and the divergence
+/// reported.
+#[derive(Clone)]
+pub struct DivergenceGuard {
+    limit: f64,
+    tripped: Option<&'static str>, // signal that diverged
+    edit: Option<(GuardField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GuardField {
+    Limit,
+}
+
+impl Field for GuardField {
+    fn label(self) -> &'static str {
+        match self {
+            GuardField::Limit => "limit",
+        }
+    }
+}
+
+impl Default for DivergenceGuard {
+    fn default() -> Self {
+        Self::new(1000.0)
+    }
+}
+
+impl DivergenceGuard {
+    pub fn new(limit: f64) -> Self {
+        Self {
+            limit,
+            tripped: None,
+            edit: None,
+        }
+    }
+
+    /// Name of the signal that diverged, if any.
+    pub fn tripped(&self) -> Option<&'static str> {
+        self.tripped
+    }
+
+    /// Checks the latest `value` of the signal `name` and returns true if it diverged.
+    pub fn check(&mut self, name: &'static str, value: f64) -> bool {
+        if !value.is_finite() || value.abs() > self.limit {
+            self.tripped.get_or_insert(name);
+            return true;
+        }
+        false
+    }
+
+    pub fn reset(&mut self) {
+        self.tripped = None;
+    }
+
+    pub fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(1)
+    }
+
+    pub fn edit(&mut self, editing: &mut Editing, k: KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    pub fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+}
+
+impl FieldList for DivergenceGuard {
+    type Field = GuardField;
+
+    fn fields(&self) -> Vec<GuardField> {
+        vec![GuardField::Limit]
+    }
+
+    fn text(&self, _field: GuardField) -> String {
+        self.limit.to_string()
+    }
+
+    fn set_value(&mut self, _field: GuardField, value: f64) {
+        // a zero limit would trip on the first sample
+        if value > 0.0 {
+            self.limit = value;
+        }
+    }
+
+    fn field_edit(&self) -> Option<&(GuardField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(GuardField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl StatefulWidgetRef for DivergenceGuard {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = self.field_lines();
+        lines.push(match self.tripped {
+            Some(signal) => Line::from(Span::styled(
+                format!("{} diverged", signal),
+                Style::default().red().add_modifier(Modifier::BOLD),
+            )),
+            None => Line::from(Span::styled(
+                "signals in range",
+                Style::default().gray().add_modifier(Modifier::BOLD),
+            )),
+        });
+        self.panel(lines).render(area, buf);
+    }
+}
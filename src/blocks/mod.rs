@@ -0,0 +1,7 @@
+pub mod actuator;
+pub mod disturbance;
+pub mod feedforward;
+pub mod guard;
+pub mod schedule;
+pub mod sensor;
+pub mod setpoint;
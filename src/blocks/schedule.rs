@@ -0,0 +1,311 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::Frame;
+use ratatui::style::Modifier;
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use crate::plants::Plant;
+use crate::utils::{NumericInput, cycle_index};
+use crate::{Editing, centered_rect};
+
+/// Width of a table column in the schedule popup.
+const CELL_WIDTH: usize = 10;
+const COLUMNS: [&str; 5] = ["parameter", "t start", "t end", "from", "to"];
+
+/// Change of one plant parameter over simulated time.
+///
+/// The parameter keeps its value until `start`, goes linearly from `from` to `to` until `end`
+/// and stays at `to` afterwards, so equal times give a step change.
+#[derive(Clone)]
+struct ScheduleEntry {
+    parameter: &'static str,
+    start: f64,
+    end: f64,
+    from: f64,
+    to: f64,
+}
+
+impl ScheduleEntry {
+    /// Value of the parameter at time `t`, none before the change starts.
+    fn value_at(&self, t: f64) -> Option<f64> {
+        if t < self.start {
+            None
+        } else if t >= self.end {
+            Some(self.to)
+        } else {
+            let w = (t - self.start) / (self.end - self.start);
+            Some(self.from + w * (self.to - self.from))
+        }
+    }
+
+    fn get(&self, column: usize) -> f64 {
+        match column {
+            1 => self.start,
+            2 => self.end,
+            3 => self.from,
+            _ => self.to,
+        }
+    }
+
+    fn set(&mut self, column: usize, value: f64) {
+        match column {
+            0 => {}
+            // the change must not end before it starts
+            1 => {
+                self.start = value;
+                self.end = self.end.max(value);
+            }
+            2 => self.end = value.max(self.start),
+            3 => self.from = value,
+            _ => self.to = value,
+        }
+    }
+}
+
+/// Schedule of plant parameter changes applied while the simulation runs, to test how a
+/// controller copes with a drifting process.
+///
+/// When several changes of the same parameter have started, the one starting last wins. The
+/// values the parameters had before the schedule first changed them are restored on reset, so
+/// every run starts from the plant as it was set up.
+#[derive(Default)]
+pub struct ParameterSchedule {
+    entries: Vec<ScheduleEntry>,
+    baseline: Vec<(&'static str, f64)>, // values before the first scheduled change
+    table_edit: Option<(usize, usize, NumericInput)>, // (row, column, cell buffer)
+}
+
+impl ParameterSchedule {
+    /// Sets the scheduled parameters of `plant` to their values at time `t`.
+    pub fn apply(&mut self, plant: &mut dyn Plant, t: f64) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let mut started = self
+            .entries
+            .iter()
+            .filter_map(|e| e.value_at(t).map(|v| (e.start, e.parameter, v)))
+            .collect::<Vec<_>>();
+        started.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut targets: Vec<(&'static str, f64)> = Vec::new();
+        for (_, parameter, value) in started {
+            targets.retain(|(p, _)| *p != parameter);
+            targets.push((parameter, value));
+        }
+
+        let parameters = plant.parameters();
+        for (parameter, value) in targets {
+            let Some(&(_, current)) = parameters.iter().find(|(p, _)| *p == parameter) else {
+                continue;
+            };
+            if current == value {
+                continue;
+            }
+            if !self.baseline.iter().any(|(p, _)| *p == parameter) {
+                self.baseline.push((parameter, current));
+            }
+            plant.set_parameter(parameter, value);
+        }
+    }
+
+    /// Restores the parameters changed by the schedule.
+    pub fn reset(&mut self, plant: &mut dyn Plant) {
+        for (parameter, value) in self.baseline.drain(..) {
+            plant.set_parameter(parameter, value);
+        }
+    }
+
+    /// Drops the schedule, whose parameters belong to a plant that is being replaced.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.baseline.clear();
+        self.table_edit = None;
+    }
+
+    /// Starts editing the first change, if any.
+    pub fn set_edit(&mut self) {
+        self.table_edit = (!self.entries.is_empty()).then(|| (0, 0, self.cell_input(0, 0)));
+    }
+
+    fn cell_input(&self, row: usize, column: usize) -> NumericInput {
+        match column {
+            0 => NumericInput::from(String::new()),
+            _ => NumericInput::from(self.entries[row].get(column).to_string()),
+        }
+    }
+
+    /// Writes the edited cell back to the schedule.
+    fn commit_cell(&mut self) {
+        let Some((row, column, input)) = self.table_edit.as_ref() else {
+            return;
+        };
+        if let Some(num) = input.as_f64() {
+            self.entries[*row].set(*column, num);
+        }
+    }
+
+    /// Adds a copy of the edited change, or a change of the first parameter of the plant when
+    /// the schedule is empty.
+    fn add_entry(&mut self, parameters: &[(&'static str, f64)]) {
+        self.commit_cell();
+        let entry = match self.table_edit.as_ref() {
+            Some((row, _, _)) => self.entries[*row].clone(),
+            None => {
+                let Some(&(parameter, value)) = parameters.first() else {
+                    return;
+                };
+                ScheduleEntry {
+                    parameter,
+                    start: 0.0,
+                    end: 0.0,
+                    from: value,
+                    to: value,
+                }
+            }
+        };
+        self.entries.push(entry);
+        let row = self.entries.len() - 1;
+        self.table_edit = Some((row, 0, self.cell_input(row, 0)));
+    }
+
+    fn remove_entry(&mut self) {
+        let Some((row, column, _)) = self.table_edit.as_ref() else {
+            return;
+        };
+        let (row, column) = (*row, *column);
+        self.entries.remove(row);
+        self.table_edit = match self.entries.len() {
+            0 => None,
+            rows => {
+                let row = row.min(rows - 1);
+                Some((row, column, self.cell_input(row, column)))
+            }
+        };
+    }
+
+    /// Edits the schedule, `parameters` being the names and current values of the parameters
+    /// of the plant.
+    pub fn edit(&mut self, editing: &mut Editing, k: KeyEvent, parameters: &[(&'static str, f64)]) {
+        match k.code {
+            KeyCode::Esc => {
+                self.table_edit = None;
+                *editing = Editing::None;
+            }
+            KeyCode::Enter => {
+                self.commit_cell();
+                self.table_edit = None;
+                *editing = Editing::None;
+            }
+            KeyCode::Char('a') => self.add_entry(parameters),
+            KeyCode::Char('x') => self.remove_entry(),
+            code => {
+                let Some((row, column, input)) = self.table_edit.as_mut() else {
+                    return;
+                };
+                let (row, column) = (*row, *column);
+                match code {
+                    KeyCode::Down | KeyCode::Up => {
+                        self.commit_cell();
+                        let row = cycle_index(row, self.entries.len(), code == KeyCode::Down);
+                        self.table_edit = Some((row, column, self.cell_input(row, column)));
+                    }
+                    KeyCode::Tab | KeyCode::BackTab => {
+                        self.commit_cell();
+                        let column = cycle_index(column, COLUMNS.len(), code == KeyCode::Tab);
+                        self.table_edit = Some((row, column, self.cell_input(row, column)));
+                    }
+                    KeyCode::Left | KeyCode::Right if column == 0 => {
+                        let entry = &mut self.entries[row];
+                        let idx = parameters
+                            .iter()
+                            .position(|(p, _)| *p == entry.parameter)
+                            .unwrap_or(0);
+                        let idx = cycle_index(idx, parameters.len(), code == KeyCode::Right);
+                        // a new parameter starts from its current value
+                        if let Some(&(parameter, value)) = parameters.get(idx) {
+                            entry.parameter = parameter;
+                            entry.from = value;
+                            entry.to = value;
+                        }
+                    }
+                    code => input.handle_key(code),
+                }
+            }
+        }
+    }
+
+    /// Renders the schedule on top of the whole frame, with the current values of the
+    /// `parameters` of the plant.
+    pub fn render_popup(&self, frame: &mut Frame, parameters: &[(&'static str, f64)]) {
+        let area = centered_rect(60, 50, frame.area());
+        let block = Block::default()
+            .title(" Parameter schedule (Tab/Up/Down move, Left/Right parameter, a add, x remove, Enter/ESC close) ")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let label_style = Style::default().gray().add_modifier(Modifier::BOLD);
+        let header = COLUMNS
+            .iter()
+            .map(|c| format!("{:<width$} ", c, width = CELL_WIDTH))
+            .collect::<String>();
+        let mut lines = vec![Line::from(Span::styled(header, label_style))];
+        for (row, entry) in self.entries.iter().enumerate() {
+            let spans = (0..COLUMNS.len())
+                .map(|column| match (self.table_edit.as_ref(), column) {
+                    (Some((r, 0, _)), 0) if *r == row => Span::styled(
+                        format!(
+                            "{:<width$} ",
+                            format!("<{}>", entry.parameter),
+                            width = CELL_WIDTH
+                        ),
+                        Style::default().cyan(),
+                    ),
+                    (Some((r, c, input)), _) if *r == row && *c == column => Span::styled(
+                        format!("{:<width$} ", input.value, width = CELL_WIDTH),
+                        Style::default().cyan(),
+                    ),
+                    (_, 0) => {
+                        Span::raw(format!("{:<width$} ", entry.parameter, width = CELL_WIDTH))
+                    }
+                    _ => Span::raw(format!(
+                        "{:<width$} ",
+                        entry.get(column),
+                        width = CELL_WIDTH
+                    )),
+                })
+                .collect::<Vec<_>>();
+            lines.push(Line::from(spans).add_modifier(Modifier::BOLD));
+        }
+        if self.entries.is_empty() {
+            let hint = if parameters.is_empty() {
+                "the plant has no scalar parameter to schedule"
+            } else {
+                "no change scheduled, a adds one"
+            };
+            lines.push(Line::from(Span::styled(hint, label_style)));
+        }
+        if !parameters.is_empty() {
+            let current = parameters
+                .iter()
+                .map(|(p, v)| format!("{} = {:.3}", p, v))
+                .collect::<Vec<_>>()
+                .join("  ");
+            lines.push(Line::default());
+            lines.push(Line::from(Span::styled(
+                format!("now: {}", current),
+                label_style,
+            )));
+        }
+        frame.render_widget(Paragraph::new(lines), inner);
+
+        if let Some((row, column, input)) = self.table_edit.as_ref() {
+            let x = inner.x + (column * (CELL_WIDTH + 1) + input.cursor) as u16;
+            let y = inner.y + 1 + *row as u16;
+            frame.set_cursor_position((x, y));
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use crossterm::event::KeyEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::Editing;
+use crate::utils::{DelayLine, Field, FieldKind, FieldList, NumericInput, Rng};
+
+/// Sensor stage measuring the plant output for the controller.
+///
+/// The true output goes through a first order lag 1 / (tau s + 1) discretized with a zero order
+/// hold, is offset by the bias and the drift accumulated since the start, gets Gaussian white
+/// noise of standard deviation `sigma` added, is rounded to the ADC resolution `quantum` (none
+/// when zero) and reaches the controller `delay` samples later. The noise is drawn from a
+/// generator seeded with `seed`, so a reset replays the same sequence.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct Sensor {
+    enabled: bool,
+    sigma: f64,     // noise standard deviation
+    seed: u64,      // seed of the noise generator
+    bias: f64,      // constant offset
+    drift: f64,     // offset growth per second
+    quantum: f64,   // ADC resolution
+    tau: f64,       // lag time constant
+    samples: usize, // delay in samples
+    Ts: f64,        // sampling time
+    rng: Rng,
+    elapsed: f64, // time since the start, for the drift
+    lagged: f64,  // output of the lag
+    measured: f64,
+    delay: DelayLine,
+    edit: Option<(SensorField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SensorField {
+    Mode,
+    Noise,
+    Seed,
+    Bias,
+    Drift,
+    Quantum,
+    Tau,
+    Delay,
+}
+
+impl Field for SensorField {
+    fn label(self) -> &'static str {
+        match self {
+            SensorField::Mode => "mode",
+            SensorField::Noise => "sigma",
+            SensorField::Seed => "seed",
+            SensorField::Bias => "bias",
+            SensorField::Drift => "drift",
+            SensorField::Quantum => "quantum",
+            SensorField::Tau => "tau",
+            SensorField::Delay => "delay",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            SensorField::Mode => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+impl Default for Sensor {
+    fn default() -> Self {
+        Self::new(0.1)
+    }
+}
+
+impl Sensor {
+    #[allow(non_snake_case)]
+    pub fn new(Ts: f64) -> Self {
+        let seed = 42;
+        Self {
+            enabled: false,
+            sigma: 0.1,
+            seed,
+            bias: 0.0,
+            drift: 0.0,
+            quantum: 0.0,
+            tau: 0.0,
+            samples: 0,
+            Ts,
+            rng: Rng::new(seed),
+            elapsed: 0.0,
+            lagged: 0.0,
+            measured: 0.0,
+            delay: DelayLine::new(0),
+            edit: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Computes the measurement the controller receives for the true plant output `y`.
+    pub fn step(&mut self, y: f64) -> f64 {
+        if !self.enabled {
+            self.lagged = y;
+            self.measured = y;
+            return y;
+        }
+        let a = if self.tau > 0.0 {
+            (-self.Ts / self.tau).exp()
+        } else {
+            0.0
+        };
+        self.lagged = a * self.lagged + (1.0 - a) * y;
+        let offset = self.bias + self.drift * self.elapsed;
+        self.elapsed += self.Ts;
+        let noisy = self.lagged + offset + self.sigma * self.rng.gaussian();
+        let sample = if self.quantum > 0.0 {
+            (noisy / self.quantum).round() * self.quantum
+        } else {
+            noisy
+        };
+        self.measured = self.delay.push(sample);
+        self.measured
+    }
+
+    pub fn reset(&mut self) {
+        self.rng = Rng::new(self.seed);
+        self.elapsed = 0.0;
+        self.lagged = 0.0;
+        self.measured = 0.0;
+        self.delay.reset();
+    }
+
+    fn value(&self, field: SensorField) -> f64 {
+        match field {
+            SensorField::Mode => 0.0,
+            SensorField::Noise => self.sigma,
+            SensorField::Seed => self.seed as f64,
+            SensorField::Bias => self.bias,
+            SensorField::Drift => self.drift,
+            SensorField::Quantum => self.quantum,
+            SensorField::Tau => self.tau,
+            SensorField::Delay => self.samples as f64,
+        }
+    }
+
+    pub fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(1)
+    }
+
+    pub fn edit(&mut self, editing: &mut Editing, k: KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    pub fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+}
+
+impl FieldList for Sensor {
+    type Field = SensorField;
+
+    fn fields(&self) -> Vec<SensorField> {
+        let mut fields = vec![SensorField::Mode];
+        if self.enabled {
+            fields.extend([
+                SensorField::Noise,
+                SensorField::Seed,
+                SensorField::Bias,
+                SensorField::Drift,
+                SensorField::Quantum,
+                SensorField::Tau,
+                SensorField::Delay,
+            ]);
+        }
+        fields
+    }
+
+    fn text(&self, field: SensorField) -> String {
+        match field {
+            SensorField::Mode => if self.enabled { "on" } else { "off" }.to_string(),
+            _ => self.value(field).to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: SensorField, value: f64) {
+        match field {
+            SensorField::Mode => {}
+            SensorField::Noise => self.sigma = value.max(0.0),
+            SensorField::Seed => {
+                self.seed = value.max(0.0) as u64;
+                self.rng = Rng::new(self.seed);
+            }
+            SensorField::Bias => self.bias = value,
+            SensorField::Drift => self.drift = value,
+            SensorField::Quantum => self.quantum = value.max(0.0),
+            SensorField::Tau => self.tau = value.max(0.0),
+            SensorField::Delay => {
+                self.samples = (value.max(0.0).round() as usize).min(DelayLine::MAX_SAMPLES);
+                self.delay.resize(self.samples);
+            }
+        }
+    }
+
+    fn cycle(&mut self, _field: SensorField, _forward: bool) {
+        self.enabled = !self.enabled;
+        // the lag and the delay start from the current output instead of rising from zero
+        self.lagged = self.measured;
+        self.delay.fill(self.measured);
+    }
+
+    fn field_edit(&self) -> Option<&(SensorField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(SensorField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl StatefulWidgetRef for Sensor {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = self.field_lines();
+        if self.is_enabled() {
+            lines.push(Line::from(Span::styled(
+                format!("y_m = {:.2}", self.measured),
+                Style::default().gray().add_modifier(Modifier::BOLD),
+            )));
+        }
+        self.panel(lines).render(area, buf);
+    }
+}
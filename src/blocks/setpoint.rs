@@ -0,0 +1,243 @@
+use crossterm::event::KeyEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::Editing;
+use crate::utils::{Field, FieldKind, FieldList, NumericInput, cycle_index};
+
+/// Integration step of the reference model relative to its natural period.
+const MODEL_STEP: f64 = 0.05;
+/// Largest natural frequency of the reference model times the sampling time, the Nyquist limit.
+/// A faster model settles within a sample and would only cost sub-steps.
+const MAX_WN_TS: f64 = std::f64::consts::PI;
+
+/// Setpoint conditioning stage between the reference and the controller.
+///
+/// The reference is either passed through a rate limiter, a first order prefilter
+/// 1 / (tau s + 1) discretized with a zero order hold, or a second order reference model
+/// wn^2 / (s^2 + 2 zeta wn s + wn^2) integrated with semi-implicit Euler sub-steps. Its output is
+/// the effective setpoint the controller tracks.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct SetpointConditioner {
+    mode: ConditioningMode,
+    rate: f64,   // largest setpoint change per second
+    tau: f64,    // prefilter time constant
+    wn: f64,     // reference model natural frequency
+    zeta: f64,   // reference model damping ratio
+    Ts: f64,     // sampling time
+    r_eff: f64,  // effective setpoint
+    dr_eff: f64, // rate of the effective setpoint, reference model only
+    edit: Option<(SetpointField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConditioningMode {
+    Off,
+    RateLimiter,
+    Prefilter,
+    ReferenceModel,
+}
+
+const MODES: [ConditioningMode; 4] = [
+    ConditioningMode::Off,
+    ConditioningMode::RateLimiter,
+    ConditioningMode::Prefilter,
+    ConditioningMode::ReferenceModel,
+];
+
+impl ConditioningMode {
+    fn label(self) -> &'static str {
+        match self {
+            ConditioningMode::Off => "off",
+            ConditioningMode::RateLimiter => "rate limiter",
+            ConditioningMode::Prefilter => "prefilter",
+            ConditioningMode::ReferenceModel => "2nd order",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SetpointField {
+    Mode,
+    Rate,
+    Tau,
+    Wn,
+    Zeta,
+}
+
+impl Field for SetpointField {
+    fn label(self) -> &'static str {
+        match self {
+            SetpointField::Mode => "mode",
+            SetpointField::Rate => "rate",
+            SetpointField::Tau => "tau",
+            SetpointField::Wn => "wn",
+            SetpointField::Zeta => "zeta",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            SetpointField::Mode => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+impl Default for SetpointConditioner {
+    fn default() -> Self {
+        Self::new(0.1)
+    }
+}
+
+impl SetpointConditioner {
+    #[allow(non_snake_case)]
+    pub fn new(Ts: f64) -> Self {
+        Self {
+            mode: ConditioningMode::Off,
+            rate: 5.0,
+            tau: 1.0,
+            wn: 2.0,
+            zeta: 1.0,
+            Ts,
+            r_eff: 0.0,
+            dr_eff: 0.0,
+            edit: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.mode != ConditioningMode::Off
+    }
+
+    /// Computes the effective setpoint for the reference `r`.
+    pub fn step(&mut self, r: f64) -> f64 {
+        match self.mode {
+            ConditioningMode::Off => {
+                self.r_eff = r;
+                self.dr_eff = 0.0;
+            }
+            ConditioningMode::RateLimiter => {
+                let max_step = self.rate * self.Ts;
+                self.r_eff += (r - self.r_eff).clamp(-max_step, max_step);
+            }
+            ConditioningMode::Prefilter => {
+                let a = if self.tau > 0.0 {
+                    (-self.Ts / self.tau).exp()
+                } else {
+                    0.0
+                };
+                self.r_eff = a * self.r_eff + (1.0 - a) * r;
+            }
+            ConditioningMode::ReferenceModel => {
+                let steps = (self.Ts * self.wn / MODEL_STEP).ceil().max(1.0);
+                let h = self.Ts / steps;
+                for _ in 0..steps as usize {
+                    let acceleration = self.wn * self.wn * (r - self.r_eff)
+                        - 2.0 * self.zeta * self.wn * self.dr_eff;
+                    self.dr_eff += h * acceleration;
+                    self.r_eff += h * self.dr_eff;
+                }
+            }
+        }
+        self.r_eff
+    }
+
+    pub fn reset(&mut self) {
+        self.r_eff = 0.0;
+        self.dr_eff = 0.0;
+    }
+
+    fn value(&self, field: SetpointField) -> f64 {
+        match field {
+            SetpointField::Mode => 0.0,
+            SetpointField::Rate => self.rate,
+            SetpointField::Tau => self.tau,
+            SetpointField::Wn => self.wn,
+            SetpointField::Zeta => self.zeta,
+        }
+    }
+
+    pub fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(1)
+    }
+
+    pub fn edit(&mut self, editing: &mut Editing, k: KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    pub fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+}
+
+impl FieldList for SetpointConditioner {
+    type Field = SetpointField;
+
+    fn fields(&self) -> Vec<SetpointField> {
+        let mut fields = vec![SetpointField::Mode];
+        match self.mode {
+            ConditioningMode::Off => {}
+            ConditioningMode::RateLimiter => fields.push(SetpointField::Rate),
+            ConditioningMode::Prefilter => fields.push(SetpointField::Tau),
+            ConditioningMode::ReferenceModel => {
+                fields.extend([SetpointField::Wn, SetpointField::Zeta])
+            }
+        }
+        fields
+    }
+
+    fn text(&self, field: SetpointField) -> String {
+        match field {
+            SetpointField::Mode => self.mode.label().to_string(),
+            _ => self.value(field).to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: SetpointField, value: f64) {
+        match field {
+            SetpointField::Mode => {}
+            SetpointField::Rate => self.rate = value.abs(),
+            SetpointField::Tau => self.tau = value.max(0.0),
+            SetpointField::Wn => self.wn = value.abs().min(MAX_WN_TS / self.Ts),
+            SetpointField::Zeta => self.zeta = value.max(0.0),
+        }
+    }
+
+    fn cycle(&mut self, _field: SetpointField, forward: bool) {
+        let idx = MODES.iter().position(|m| *m == self.mode).unwrap_or(0);
+        self.mode = MODES[cycle_index(idx, MODES.len(), forward)];
+        self.dr_eff = 0.0;
+    }
+
+    fn field_edit(&self) -> Option<&(SetpointField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(SetpointField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl StatefulWidgetRef for SetpointConditioner {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = self.field_lines();
+        if self.is_enabled() {
+            lines.push(Line::from(Span::styled(
+                format!("r_eff = {:.2}", self.r_eff),
+                Style::default().gray().add_modifier(Modifier::BOLD),
+            )));
+        }
+        self.panel(lines).render(area, buf);
+    }
+}
@@ -0,0 +1,446 @@
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::Modifier;
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, FrameExt, Paragraph, StatefulWidgetRef, Widget};
+
+use crate::controllers::{Controller, status_line};
+use crate::utils::{Field, FieldList, NumericInput, cycle_index};
+use crate::{Editing, centered_rect, register_controller};
+
+const CONTROLLER_NAME: &str = "Fuzzy PI (Sugeno)";
+
+/// Number of linguistic terms of each input.
+const TERMS: usize = 5;
+const TERM_NAMES: [&str; TERMS] = ["NB", "NS", "ZE", "PS", "PB"];
+/// Width of the row labels and of a table column in the rule popup.
+const LABEL_WIDTH: usize = 6;
+const CELL_WIDTH: usize = 7;
+/// Rows of the popup table: the peaks of both inputs followed by the rule rows.
+const TABLE_ROWS: usize = 2 + TERMS;
+
+/// Incremental fuzzy PI controller with zero order Sugeno inference.
+///
+/// The error and its rate are scaled by `Ke` and `Kde` onto the universe [-1, 1], where each is
+/// covered by five triangular membership functions given by their peaks: a function rises from
+/// the previous peak and falls to the next one, the outer ones saturate. Every pair of terms
+/// fires the singleton of the rule table with the product of the memberships and the weighted
+/// average of the singletons, scaled by `Ku`, is the rate of change of the controller output.
+///
+/// The default peaks and rules give the singleton (e + de) / 2, so inside the universe the
+/// controller reduces to a PI with Kp = Ku Kde / 2 and Ki = Ku Ke / 2.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct FuzzyPIController {
+    Ke: f64,                      // error scaling
+    Kde: f64,                     // error rate scaling
+    Ku: f64,                      // output rate scaling
+    e_peaks: [f64; TERMS],        // membership peaks of the error terms
+    de_peaks: [f64; TERMS],       // membership peaks of the error rate terms
+    rules: [[f64; TERMS]; TERMS], // singletons, rows by error term, columns by rate term
+    u: f64,                       // controller output
+    e: f64,                       // previous error
+    y: f64,                       // current output of the system
+    r: f64,                       // set point (reference input)
+    Ts: f64,                      // sampling time
+    x: f64,                       // current time
+    edit: Option<(FuzzyField, NumericInput)>,
+    table_edit: Option<(usize, usize, NumericInput)>, // (row, column, cell buffer)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FuzzyField {
+    Ke,
+    Kde,
+    Ku,
+}
+
+const FIELDS: [FuzzyField; 3] = [FuzzyField::Ke, FuzzyField::Kde, FuzzyField::Ku];
+
+impl Field for FuzzyField {
+    fn label(self) -> &'static str {
+        match self {
+            FuzzyField::Ke => "Ke",
+            FuzzyField::Kde => "Kde",
+            FuzzyField::Ku => "Ku",
+        }
+    }
+}
+
+impl Default for FuzzyPIController {
+    fn default() -> Self {
+        let peaks = [-1.0, -0.5, 0.0, 0.5, 1.0];
+        let mut rules = [[0.0; TERMS]; TERMS];
+        for (i, row) in rules.iter_mut().enumerate() {
+            for (j, rule) in row.iter_mut().enumerate() {
+                *rule = ((i + j) as f64 / 4.0 - 1.0).clamp(-1.0, 1.0);
+            }
+        }
+        FuzzyPIController::new(0.1, 0.1, 10.0, peaks, peaks, rules, 0.1)
+    }
+}
+
+/// Memberships of `v` in the terms with the given sorted peaks.
+fn memberships(v: f64, peaks: &[f64; TERMS]) -> [f64; TERMS] {
+    let mut mu = [0.0; TERMS];
+    if v <= peaks[0] {
+        mu[0] = 1.0;
+    } else if v >= peaks[TERMS - 1] {
+        mu[TERMS - 1] = 1.0;
+    } else {
+        let k = (0..TERMS - 1)
+            .find(|k| v <= peaks[k + 1])
+            .unwrap_or(TERMS - 2);
+        let width = peaks[k + 1] - peaks[k];
+        let w = if width > 0.0 {
+            (v - peaks[k]) / width
+        } else {
+            1.0
+        };
+        mu[k] = 1.0 - w;
+        mu[k + 1] = w;
+    }
+    mu
+}
+
+impl FuzzyPIController {
+    #[allow(non_snake_case)]
+    pub fn new(
+        Ke: f64,
+        Kde: f64,
+        Ku: f64,
+        e_peaks: [f64; TERMS],
+        de_peaks: [f64; TERMS],
+        rules: [[f64; TERMS]; TERMS],
+        Ts: f64,
+    ) -> Self {
+        Self {
+            Ke,
+            Kde,
+            Ku,
+            e_peaks,
+            de_peaks,
+            rules,
+            u: 0.0,
+            e: 0.0,
+            y: 0.0,
+            r: 0.0,
+            Ts,
+            x: 0.0,
+            edit: None,
+            table_edit: None,
+        }
+    }
+
+    /// Normalized controller output rate for the normalized error and error rate.
+    fn infer(&self, e: f64, de: f64) -> f64 {
+        let mu_e = memberships(e, &self.e_peaks);
+        let mu_de = memberships(de, &self.de_peaks);
+        let mut weights = 0.0;
+        let mut output = 0.0;
+        for (i, row) in self.rules.iter().enumerate() {
+            for (j, rule) in row.iter().enumerate() {
+                let w = mu_e[i] * mu_de[j];
+                weights += w;
+                output += w * rule;
+            }
+        }
+        if weights > 0.0 { output / weights } else { 0.0 }
+    }
+
+    fn cell(&self, row: usize, column: usize) -> f64 {
+        match row {
+            0 => self.e_peaks[column],
+            1 => self.de_peaks[column],
+            _ => self.rules[row - 2][column],
+        }
+    }
+
+    fn cell_input(&self, row: usize, column: usize) -> NumericInput {
+        NumericInput::from(self.cell(row, column).to_string())
+    }
+
+    /// Writes the edited cell back, keeping the membership peaks sorted.
+    fn commit_cell(&mut self) {
+        let Some((row, column, input)) = self.table_edit.as_ref() else {
+            return;
+        };
+        let (row, column) = (*row, *column);
+        let Some(num) = input.as_f64() else {
+            return;
+        };
+        match row {
+            0 => {
+                self.e_peaks[column] = num;
+                self.e_peaks.sort_by(f64::total_cmp);
+            }
+            1 => {
+                self.de_peaks[column] = num;
+                self.de_peaks.sort_by(f64::total_cmp);
+            }
+            _ => self.rules[row - 2][column] = num,
+        }
+    }
+
+    fn edit_table(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        let Some((row, column, input)) = self.table_edit.as_mut() else {
+            return;
+        };
+        let (row, column) = (*row, *column);
+
+        match k.code {
+            KeyCode::Esc => {
+                self.table_edit = None;
+                *editing = Editing::Controller;
+            }
+            KeyCode::Enter => {
+                self.commit_cell();
+                self.table_edit = None;
+                *editing = Editing::Controller;
+            }
+            KeyCode::Down | KeyCode::Up => {
+                self.commit_cell();
+                let row = cycle_index(row, TABLE_ROWS, k.code == KeyCode::Down);
+                self.table_edit = Some((row, column, self.cell_input(row, column)));
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.commit_cell();
+                let column = cycle_index(column, TERMS, k.code == KeyCode::Tab);
+                self.table_edit = Some((row, column, self.cell_input(row, column)));
+            }
+            code => input.handle_key(code),
+        }
+    }
+
+    fn value(&self, field: FuzzyField) -> f64 {
+        match field {
+            FuzzyField::Ke => self.Ke,
+            FuzzyField::Kde => self.Kde,
+            FuzzyField::Ku => self.Ku,
+        }
+    }
+
+    /// Control surface over the normalized universe, error to the right and rate upwards,
+    /// drawn with one colored cell per grid point.
+    fn heatmap(&self, area: Rect) -> Vec<Line<'static>> {
+        let columns = (area.width as usize / 2).clamp(2, 21);
+        let rows = (area.height as usize).saturating_sub(2).clamp(2, 11);
+        let surface: Vec<Vec<f64>> = (0..rows)
+            .map(|j| {
+                let de = 1.0 - 2.0 * j as f64 / (rows - 1) as f64;
+                (0..columns)
+                    .map(|i| self.infer(-1.0 + 2.0 * i as f64 / (columns - 1) as f64, de))
+                    .collect()
+            })
+            .collect();
+        let (min, max) = surface
+            .iter()
+            .flatten()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(*v), hi.max(*v))
+            });
+        let range = if max > min { max - min } else { 1.0 };
+
+        let label_style = Style::default().gray().add_modifier(Modifier::BOLD);
+        let mut lines = vec![Line::from(Span::styled(
+            "surface (e ->, de ^)",
+            label_style,
+        ))];
+        for row in surface {
+            let spans = row
+                .into_iter()
+                .map(|v| {
+                    let t = (v - min) / range;
+                    let color = Color::Rgb((255.0 * t) as u8, 64, (255.0 * (1.0 - t)) as u8);
+                    Span::styled("  ", Style::default().bg(color))
+                })
+                .collect::<Vec<_>>();
+            lines.push(Line::from(spans));
+        }
+        lines.push(Line::from(Span::styled(
+            format!("blue {:.2} .. red {:.2}", min, max),
+            label_style,
+        )));
+        lines
+    }
+}
+
+impl FieldList for FuzzyPIController {
+    type Field = FuzzyField;
+
+    fn fields(&self) -> Vec<FuzzyField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: FuzzyField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: FuzzyField, value: f64) {
+        match field {
+            FuzzyField::Ke => self.Ke = value,
+            FuzzyField::Kde => self.Kde = value,
+            FuzzyField::Ku => self.Ku = value,
+        }
+    }
+
+    fn field_edit(&self) -> Option<&(FuzzyField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(FuzzyField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Controller for FuzzyPIController {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(3)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        if let Editing::ControllerPopup = editing {
+            self.edit_table(editing, k);
+            return;
+        }
+        match k.code {
+            KeyCode::Tab => {
+                self.table_edit = Some((0, 0, self.cell_input(0, 0)));
+                *editing = Editing::ControllerPopup;
+            }
+            _ => self.edit_fields(editing, k),
+        }
+    }
+
+    fn set_plant_output(&mut self, y: f64) {
+        self.y = y;
+    }
+    fn set_set_point(&mut self, r: f64) {
+        self.r = r;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.u = 0.0;
+        self.e = 0.0;
+        self.r = 0.0;
+        self.y = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut (bool, Editing)) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+
+    fn render_popup(&self, frame: &mut Frame) {
+        let area = centered_rect(70, 50, frame.area());
+        let block = Block::default()
+            .title(" Fuzzy rules (Tab/Up/Down move, Enter/ESC close) ")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        let [table_area, heatmap_area] = Layout::horizontal([
+            Constraint::Length((LABEL_WIDTH + TERMS * (CELL_WIDTH + 1) + 2) as u16),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+
+        let label_style = Style::default().gray().add_modifier(Modifier::BOLD);
+        let header = std::iter::once(format!("{:<width$}", "", width = LABEL_WIDTH))
+            .chain(
+                TERM_NAMES
+                    .iter()
+                    .map(|t| format!("{:<width$} ", t, width = CELL_WIDTH)),
+            )
+            .collect::<String>();
+        let table_row = |row: usize, label: &str| {
+            let mut spans = vec![Span::styled(
+                format!("{:<width$}", label, width = LABEL_WIDTH),
+                label_style,
+            )];
+            spans.extend((0..TERMS).map(|column| match self.table_edit.as_ref() {
+                Some((r, c, input)) if *r == row && *c == column => Span::styled(
+                    format!("{:<width$} ", input.value, width = CELL_WIDTH),
+                    Style::default().cyan(),
+                ),
+                _ => Span::raw(format!(
+                    "{:<width$} ",
+                    self.cell(row, column),
+                    width = CELL_WIDTH
+                )),
+            }));
+            Line::from(spans).add_modifier(Modifier::BOLD)
+        };
+        let mut lines = vec![
+            Line::from(Span::styled(header, label_style)),
+            table_row(0, "e"),
+            table_row(1, "de"),
+            Line::from(Span::styled("rules: e rows, de columns", label_style)),
+        ];
+        for (term, name) in TERM_NAMES.iter().enumerate() {
+            lines.push(table_row(2 + term, name));
+        }
+        frame.render_widget(Paragraph::new(lines), table_area);
+        frame.render_widget(Paragraph::new(self.heatmap(heatmap_area)), heatmap_area);
+
+        if let Some((row, column, input)) = self.table_edit.as_ref() {
+            // the rule rows are below the heading line
+            let line = if *row < 2 { row + 1 } else { row + 2 };
+            let x = table_area.x + (LABEL_WIDTH + column * (CELL_WIDTH + 1) + input.cursor) as u16;
+            let y = table_area.y + line as u16;
+            frame.set_cursor_position((x, y));
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        CONTROLLER_NAME
+    }
+}
+
+impl Iterator for FuzzyPIController {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let e = self.r - self.y; // error = set point - plant_output
+        let de = (e - self.e) / self.Ts;
+        self.e = e;
+        let rate = self.infer(self.Ke * e, self.Kde * de);
+        self.u += self.Ku * rate * self.Ts;
+
+        let point = (self.x, self.u);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for FuzzyPIController {
+    type State = (bool, Editing);
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let editing = self.edit.is_some();
+        let mut lines = vec![
+            status_line(state.0, editing),
+            Line::from(Span::styled(
+                "Fuzzy PI",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ];
+        lines.extend(self.field_lines());
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("rules: {}x{} ", TERMS, TERMS),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("<Tab>").blue().bold(),
+        ]));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_controller!(FuzzyPIController, CONTROLLER_NAME);
@@ -0,0 +1,436 @@
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, FrameExt, Paragraph, StatefulWidgetRef, Widget};
+
+use crate::controllers::{Controller, status_line};
+use crate::utils::{Field, FieldKind, FieldList, NumericInput, cycle_index};
+use crate::{Editing, centered_rect, register_controller};
+
+const CONTROLLER_NAME: &str = "Gain-Scheduled PID";
+
+/// Width of a table column in the schedule popup.
+const CELL_WIDTH: usize = 10;
+const COLUMNS: [&str; 4] = ["x", "Kp", "Ki", "Kd"];
+
+/// PID controller whose gains are interpolated from a schedule table.
+///
+/// The scheduling variable is either the reference or the measured plant output. Gains are
+/// linearly interpolated between the breakpoints and held constant outside of the table.
+///
+/// The integral term integrates `Ki * e` rather than multiplying the integral of `e` by `Ki`, and
+/// absorbs the change of `Kp * e` when `Kp` changes, so the controller output stays continuous
+/// (bumpless) when the gains change.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct GainScheduledPID {
+    schedule: Vec<GainPoint>, // breakpoints sorted by the scheduling variable
+    source: ScheduleSource,
+    N: f64,                 // derivative filter coefficient
+    gains: (f64, f64, f64), // currently applied (Kp, Ki, Kd)
+    i: f64,                 // integral term
+    d: f64,                 // filtered derivative term
+    e: f64,                 // previous error
+    y: f64,                 // current output of the system
+    r: f64,                 // set point (reference input)
+    Ts: f64,                // sampling time
+    x: f64,                 // current time
+    edit: Option<(GainScheduledField, NumericInput)>,
+    table_edit: Option<(usize, usize, NumericInput)>, // (row, column, cell buffer)
+}
+
+#[allow(non_snake_case)]
+#[derive(Clone, Copy)]
+pub struct GainPoint {
+    at: f64,
+    Kp: f64,
+    Ki: f64,
+    Kd: f64,
+}
+
+impl GainPoint {
+    fn get(&self, column: usize) -> f64 {
+        match column {
+            0 => self.at,
+            1 => self.Kp,
+            2 => self.Ki,
+            _ => self.Kd,
+        }
+    }
+
+    fn set(&mut self, column: usize, value: f64) {
+        match column {
+            0 => self.at = value,
+            1 => self.Kp = value,
+            2 => self.Ki = value,
+            _ => self.Kd = value,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ScheduleSource {
+    Reference,
+    Output,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GainScheduledField {
+    Source,
+    N,
+}
+
+const FIELDS: [GainScheduledField; 2] = [GainScheduledField::Source, GainScheduledField::N];
+
+impl Field for GainScheduledField {
+    fn label(self) -> &'static str {
+        match self {
+            GainScheduledField::Source => "source",
+            GainScheduledField::N => "N",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            GainScheduledField::Source => FieldKind::Choice,
+            GainScheduledField::N => FieldKind::Number,
+        }
+    }
+}
+
+impl Default for GainScheduledPID {
+    fn default() -> Self {
+        let schedule = vec![
+            GainPoint {
+                at: 0.0,
+                Kp: 0.8,
+                Ki: 2.0,
+                Kd: 2.0,
+            },
+            GainPoint {
+                at: 10.0,
+                Kp: 0.4,
+                Ki: 1.0,
+                Kd: 1.0,
+            },
+        ];
+        GainScheduledPID::new(schedule, ScheduleSource::Reference, 5.0, 0.1)
+    }
+}
+
+impl GainScheduledPID {
+    #[allow(non_snake_case)]
+    pub fn new(mut schedule: Vec<GainPoint>, source: ScheduleSource, N: f64, Ts: f64) -> Self {
+        schedule.sort_by(|a, b| a.at.total_cmp(&b.at));
+        let mut controller = Self {
+            schedule,
+            source,
+            N,
+            gains: (0.0, 0.0, 0.0),
+            i: 0.0,
+            d: 0.0,
+            e: 0.0,
+            y: 0.0,
+            r: 0.0,
+            Ts,
+            x: 0.0,
+            edit: None,
+            table_edit: None,
+        };
+        controller.gains = controller.interpolate(0.0);
+        controller
+    }
+
+    /// Gains at the given value of the scheduling variable.
+    fn interpolate(&self, v: f64) -> (f64, f64, f64) {
+        let (Some(first), Some(last)) = (self.schedule.first(), self.schedule.last()) else {
+            return (0.0, 0.0, 0.0);
+        };
+        if v <= first.at {
+            return (first.Kp, first.Ki, first.Kd);
+        }
+        if v >= last.at {
+            return (last.Kp, last.Ki, last.Kd);
+        }
+        for pair in self.schedule.windows(2) {
+            let (lo, hi) = (pair[0], pair[1]);
+            if v <= hi.at {
+                let w = if hi.at > lo.at {
+                    (v - lo.at) / (hi.at - lo.at)
+                } else {
+                    1.0
+                };
+                return (
+                    lo.Kp + w * (hi.Kp - lo.Kp),
+                    lo.Ki + w * (hi.Ki - lo.Ki),
+                    lo.Kd + w * (hi.Kd - lo.Kd),
+                );
+            }
+        }
+        (last.Kp, last.Ki, last.Kd)
+    }
+
+    fn cell_input(&self, row: usize, column: usize) -> NumericInput {
+        NumericInput::from(self.schedule[row].get(column).to_string())
+    }
+
+    /// Writes the edited cell back to the schedule and returns the row it ended up in after
+    /// sorting the breakpoints.
+    fn commit_cell(&mut self) -> usize {
+        let Some((row, column, input)) = self.table_edit.as_ref() else {
+            return 0;
+        };
+        let (row, column) = (*row, *column);
+        if let Some(num) = input.as_f64() {
+            self.schedule[row].set(column, num);
+        }
+        let point = self.schedule[row];
+        self.sort_schedule(point).unwrap_or(row)
+    }
+
+    /// Sorts the breakpoints and returns the new row of `point`.
+    fn sort_schedule(&mut self, point: GainPoint) -> Option<usize> {
+        self.schedule.sort_by(|a, b| a.at.total_cmp(&b.at));
+        self.schedule.iter().position(|p| {
+            p.at == point.at && p.Kp == point.Kp && p.Ki == point.Ki && p.Kd == point.Kd
+        })
+    }
+
+    fn edit_table(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        let Some((row, column, input)) = self.table_edit.as_mut() else {
+            return;
+        };
+        let column = *column;
+        let rows = self.schedule.len();
+
+        match k.code {
+            KeyCode::Esc => {
+                self.table_edit = None;
+                *editing = Editing::Controller;
+            }
+            KeyCode::Enter => {
+                self.commit_cell();
+                self.table_edit = None;
+                *editing = Editing::Controller;
+            }
+            KeyCode::Down | KeyCode::Up => {
+                let row = self.commit_cell();
+                let row = cycle_index(row, rows, k.code == KeyCode::Down);
+                self.table_edit = Some((row, column, self.cell_input(row, column)));
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                let row = self.commit_cell();
+                let column = cycle_index(column, COLUMNS.len(), k.code == KeyCode::Tab);
+                self.table_edit = Some((row, column, self.cell_input(row, column)));
+            }
+            KeyCode::Char('a') => {
+                let row = self.commit_cell();
+                let mut point = self.schedule[row];
+                point.at += 1.0;
+                self.schedule.push(point);
+                let row = self.sort_schedule(point).unwrap_or(row);
+                self.table_edit = Some((row, 0, self.cell_input(row, 0)));
+            }
+            KeyCode::Char('x') if rows > 1 => {
+                let row = *row;
+                self.schedule.remove(row);
+                let row = row.min(rows - 2);
+                self.table_edit = Some((row, column, self.cell_input(row, column)));
+            }
+            code => input.handle_key(code),
+        }
+    }
+}
+
+impl FieldList for GainScheduledPID {
+    type Field = GainScheduledField;
+
+    fn fields(&self) -> Vec<GainScheduledField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: GainScheduledField) -> String {
+        match field {
+            GainScheduledField::Source => match self.source {
+                ScheduleSource::Reference => "reference".to_string(),
+                ScheduleSource::Output => "output".to_string(),
+            },
+            GainScheduledField::N => self.N.to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: GainScheduledField, value: f64) {
+        if field == GainScheduledField::N {
+            self.N = value;
+        }
+    }
+
+    fn cycle(&mut self, _field: GainScheduledField, _forward: bool) {
+        self.source = match self.source {
+            ScheduleSource::Reference => ScheduleSource::Output,
+            ScheduleSource::Output => ScheduleSource::Reference,
+        };
+    }
+
+    fn field_edit(&self) -> Option<&(GainScheduledField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(GainScheduledField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Controller for GainScheduledPID {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(3)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        if let Editing::ControllerPopup = editing {
+            self.edit_table(editing, k);
+            return;
+        }
+        match k.code {
+            KeyCode::Tab => {
+                self.table_edit = Some((0, 0, self.cell_input(0, 0)));
+                *editing = Editing::ControllerPopup;
+            }
+            _ => self.edit_fields(editing, k),
+        }
+    }
+
+    fn set_plant_output(&mut self, y: f64) {
+        self.y = y;
+    }
+    fn set_set_point(&mut self, r: f64) {
+        self.r = r;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.i = 0.0;
+        self.d = 0.0;
+        self.e = 0.0;
+        self.r = 0.0;
+        self.y = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut (bool, Editing)) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+
+    fn render_popup(&self, frame: &mut Frame) {
+        let area = centered_rect(50, 40, frame.area());
+        let block = Block::default()
+            .title(" Gain schedule (Tab/Up/Down move, a add, x remove, Enter/ESC close) ")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let header = COLUMNS
+            .iter()
+            .map(|c| format!("{:<width$} ", c, width = CELL_WIDTH))
+            .collect::<String>();
+        let mut lines = vec![Line::from(Span::styled(
+            header,
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        ))];
+        for (row, point) in self.schedule.iter().enumerate() {
+            let spans = (0..COLUMNS.len())
+                .map(|column| match self.table_edit.as_ref() {
+                    Some((r, c, input)) if *r == row && *c == column => Span::styled(
+                        format!("{:<width$} ", input.value, width = CELL_WIDTH),
+                        Style::default().cyan(),
+                    ),
+                    _ => Span::raw(format!(
+                        "{:<width$} ",
+                        point.get(column),
+                        width = CELL_WIDTH
+                    )),
+                })
+                .collect::<Vec<_>>();
+            lines.push(Line::from(spans).add_modifier(Modifier::BOLD));
+        }
+        frame.render_widget(Paragraph::new(lines), inner);
+
+        if let Some((row, column, input)) = self.table_edit.as_ref() {
+            let x = inner.x + (column * (CELL_WIDTH + 1) + input.cursor) as u16;
+            let y = inner.y + 1 + *row as u16;
+            frame.set_cursor_position((x, y));
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        CONTROLLER_NAME
+    }
+}
+
+impl Iterator for GainScheduledPID {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let v = match self.source {
+            ScheduleSource::Reference => self.r,
+            ScheduleSource::Output => self.y,
+        };
+        let (kp_old, _, _) = self.gains;
+        self.gains = self.interpolate(v);
+        let (kp, ki, kd) = self.gains;
+
+        let e = self.r - self.y; // error = set point - plant_output
+        // rebase the integrator so the proportional term does not jump, once there is an output
+        if self.x > 0.0 {
+            self.i += (kp_old - kp) * e;
+        }
+        self.i += ki * self.Ts * e;
+        // backward Euler discretization of Kd * N s / (s + N)
+        self.d = (self.d + kd * self.N * (e - self.e)) / (1.0 + self.N * self.Ts);
+        self.e = e;
+
+        let point = (self.x, kp * e + self.i + self.d);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for GainScheduledPID {
+    type State = (bool, Editing);
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let editing = self.edit.is_some();
+        let (kp, ki, kd) = self.gains;
+        let gains_style = Style::default().gray().add_modifier(Modifier::BOLD);
+        let mut lines = vec![
+            status_line(state.0, editing),
+            Line::from(Span::styled(
+                "Gain-scheduled PID",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ];
+        lines.extend(self.field_lines());
+        lines.extend([
+            Line::from(Span::styled(format!("Kp = {:.3}", kp), gains_style)),
+            Line::from(Span::styled(format!("Ki = {:.3}", ki), gains_style)),
+            Line::from(Span::styled(format!("Kd = {:.3}", kd), gains_style)),
+            Line::from(vec![
+                Span::styled(
+                    format!("table: {} rows ", self.schedule.len()),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("<Tab>").blue().bold(),
+            ]),
+        ]);
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_controller!(GainScheduledPID, CONTROLLER_NAME);
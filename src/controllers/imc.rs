@@ -0,0 +1,314 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::controllers::{Controller, status_line};
+use crate::utils::{DelayLine, Field, FieldKind, FieldList, LeadLag, NumericInput};
+use crate::{Editing, register_controller};
+
+const CONTROLLER_NAME: &str = "Internal Model Control (IMC)";
+
+/// Internal model controller with a first or second order plus dead time model
+///
+///   G_m = K_m e^(-theta s) / ((tau1 s + 1) (tau2 s + 1))
+///
+/// and the controller Q = (tau1 s + 1) (tau2 s + 1) / (K_m (lambda s + 1)^n), the inverse of the
+/// invertible part of the model made proper by a filter of the model order n. The controller acts
+/// on the reference corrected by the mismatch between the plant and the model output:
+///
+///   u = Q (r - (y - y_m))
+///
+/// so the filter time constant `lambda` is the only tuning knob. The panel shows the classic
+/// IMC-PID equivalent obtained with a first order filter and e^(-theta s) ~ 1 - theta s.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct IMCController {
+    order: ModelOrder,
+    K_m: f64,    // model static gain
+    tau1: f64,   // first model time constant
+    tau2: f64,   // second model time constant, second order model only
+    theta: f64,  // model dead time
+    lambda: f64, // filter time constant
+    q: (LeadLag, LeadLag),
+    m: (f64, f64), // model states (zero order hold discretization of each lag)
+    y_m: f64,      // delayed model output
+    delay: DelayLine,
+    y: f64,  // current output of the system
+    r: f64,  // set point (reference input)
+    Ts: f64, // sampling time
+    x: f64,  // current time
+    edit: Option<(IMCField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ModelOrder {
+    First,
+    Second,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum IMCField {
+    Order,
+    Gain,
+    Tau1,
+    Tau2,
+    Theta,
+    Lambda,
+}
+
+impl Field for IMCField {
+    fn label(self) -> &'static str {
+        match self {
+            IMCField::Order => "order",
+            IMCField::Gain => "K_m",
+            IMCField::Tau1 => "tau1",
+            IMCField::Tau2 => "tau2",
+            IMCField::Theta => "theta",
+            IMCField::Lambda => "lambda",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            IMCField::Order => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+impl Default for IMCController {
+    fn default() -> Self {
+        IMCController::new(ModelOrder::Second, 1.0, 1.0, 1.0, 0.0, 1.0, 0.1)
+    }
+}
+
+/// Pole of the zero order hold discretization of 1 / (tau s + 1).
+fn zoh_pole(tau: f64, ts: f64) -> f64 {
+    if tau > 0.0 { (-ts / tau).exp() } else { 0.0 }
+}
+
+impl IMCController {
+    #[allow(non_snake_case)]
+    pub fn new(
+        order: ModelOrder,
+        K_m: f64,
+        tau1: f64,
+        tau2: f64,
+        theta: f64,
+        lambda: f64,
+        Ts: f64,
+    ) -> Self {
+        let mut controller = Self {
+            order,
+            K_m,
+            tau1,
+            tau2,
+            theta,
+            lambda,
+            q: (LeadLag::default(), LeadLag::default()),
+            m: (0.0, 0.0),
+            y_m: 0.0,
+            delay: DelayLine::new(DelayLine::samples_for(theta, Ts)),
+            y: 0.0,
+            r: 0.0,
+            Ts,
+            x: 0.0,
+            edit: None,
+        };
+        controller.update_model();
+        controller
+    }
+
+    fn update_model(&mut self) {
+        let k = if self.K_m != 0.0 { 1.0 / self.K_m } else { 0.0 };
+        self.q.0.retune(k, self.tau1, self.lambda, self.Ts);
+        match self.order {
+            ModelOrder::First => self.q.1.retune(1.0, 0.0, 0.0, self.Ts),
+            ModelOrder::Second => self.q.1.retune(1.0, self.tau2, self.lambda, self.Ts),
+        }
+        self.delay
+            .resize(DelayLine::samples_for(self.theta, self.Ts));
+    }
+
+    /// Equivalent (Kp, Ki, Kd) of the IMC-PID tuning rules.
+    fn pid_gains(&self) -> Option<(f64, f64, f64)> {
+        let k = self.K_m * (self.lambda + self.theta);
+        if k == 0.0 {
+            return None;
+        }
+        Some(match self.order {
+            ModelOrder::First => (self.tau1 / k, 1.0 / k, 0.0),
+            ModelOrder::Second => (
+                (self.tau1 + self.tau2) / k,
+                1.0 / k,
+                self.tau1 * self.tau2 / k,
+            ),
+        })
+    }
+
+    fn value(&self, field: IMCField) -> f64 {
+        match field {
+            IMCField::Order => match self.order {
+                ModelOrder::First => 1.0,
+                ModelOrder::Second => 2.0,
+            },
+            IMCField::Gain => self.K_m,
+            IMCField::Tau1 => self.tau1,
+            IMCField::Tau2 => self.tau2,
+            IMCField::Theta => self.theta,
+            IMCField::Lambda => self.lambda,
+        }
+    }
+}
+
+impl FieldList for IMCController {
+    type Field = IMCField;
+
+    fn fields(&self) -> Vec<IMCField> {
+        match self.order {
+            ModelOrder::First => vec![
+                IMCField::Order,
+                IMCField::Gain,
+                IMCField::Tau1,
+                IMCField::Theta,
+                IMCField::Lambda,
+            ],
+            ModelOrder::Second => vec![
+                IMCField::Order,
+                IMCField::Gain,
+                IMCField::Tau1,
+                IMCField::Tau2,
+                IMCField::Theta,
+                IMCField::Lambda,
+            ],
+        }
+    }
+
+    fn text(&self, field: IMCField) -> String {
+        match (field, self.order) {
+            (IMCField::Order, ModelOrder::First) => "first".to_string(),
+            (IMCField::Order, ModelOrder::Second) => "second".to_string(),
+            _ => self.value(field).to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: IMCField, value: f64) {
+        match field {
+            IMCField::Order => {}
+            IMCField::Gain => self.K_m = value,
+            IMCField::Tau1 => self.tau1 = value.max(0.0),
+            IMCField::Tau2 => self.tau2 = value.max(0.0),
+            IMCField::Theta => self.theta = value.max(0.0),
+            IMCField::Lambda => self.lambda = value.max(0.0),
+        }
+        self.update_model();
+    }
+
+    fn cycle(&mut self, _field: IMCField, _forward: bool) {
+        self.order = match self.order {
+            ModelOrder::First => ModelOrder::Second,
+            ModelOrder::Second => ModelOrder::First,
+        };
+        self.update_model();
+    }
+
+    fn field_edit(&self) -> Option<&(IMCField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(IMCField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Controller for IMCController {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(3)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_plant_output(&mut self, y: f64) {
+        self.y = y;
+    }
+    fn set_set_point(&mut self, r: f64) {
+        self.r = r;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.q.0.reset();
+        self.q.1.reset();
+        self.delay.reset();
+        self.m = (0.0, 0.0);
+        self.y_m = 0.0;
+        self.x = 0.0;
+        self.y = 0.0;
+        self.r = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut (bool, Editing)) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+
+    fn name(&self) -> &'static str {
+        CONTROLLER_NAME
+    }
+}
+
+impl Iterator for IMCController {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        // y results from the previous input, as does the current model output
+        let feedback = self.r - (self.y - self.y_m);
+        let controller_output = self.q.1.step(self.q.0.step(feedback));
+
+        let a1 = zoh_pole(self.tau1, self.Ts);
+        self.m.0 = a1 * self.m.0 + (1.0 - a1) * self.K_m * controller_output;
+        self.m.1 = match self.order {
+            ModelOrder::First => self.m.0,
+            ModelOrder::Second => {
+                let a2 = zoh_pole(self.tau2, self.Ts);
+                a2 * self.m.1 + (1.0 - a2) * self.m.0
+            }
+        };
+        self.y_m = self.delay.push(self.m.1);
+
+        let point = (self.x, controller_output);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for IMCController {
+    type State = (bool, Editing);
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let editing = self.edit.is_some();
+        let mut lines = vec![
+            status_line(state.0, editing),
+            Line::from(Span::styled(
+                "Internal model control",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ];
+        lines.extend(self.field_lines());
+        let gains_style = Style::default().gray().add_modifier(Modifier::BOLD);
+        lines.push(Line::from(Span::styled("equivalent PID:", gains_style)));
+        let gains = match self.pid_gains() {
+            Some((kp, ki, kd)) => format!("Kp {:.2} Ki {:.2} Kd {:.2}", kp, ki, kd),
+            None => "undefined".to_string(),
+        };
+        lines.push(Line::from(Span::styled(gains, gains_style)));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_controller!(IMCController, CONTROLLER_NAME);
@@ -0,0 +1,245 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::controllers::{Controller, status_line};
+use crate::linalg::{Matrix, StateSpace, dlqr};
+use crate::utils::{Field, FieldList, NumericInput};
+use crate::{Editing, register_controller};
+
+const CONTROLLER_NAME: &str = "LQR State Feedback with Integral Action";
+
+/// Discrete linear quadratic regulator with integral action.
+///
+/// The plant model is augmented with the integrator z[k+1] = z[k] + Ts (r - y[k]) and the gain
+/// [Kx Kz] minimizing sum(x'Qx + q_i z^2 + r_u u^2), with Q = q_y C'C, is computed from the
+/// discrete algebraic Riccati equation. The control law is u = -Kx x - Kz z.
+///
+/// The plant state and model are provided by the plant each tick and the gain is redesigned
+/// whenever the model changes. Plants without a linear model leave the output at zero.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct LQRController {
+    q_y: f64, // output weight
+    q_i: f64, // integrated error weight
+    r_u: f64, // input weight
+    model: Option<StateSpace>,
+    K: Option<Matrix>, // [Kx Kz]
+    x_p: Vec<f64>,     // plant state
+    z: f64,            // integrated error
+    y: f64,            // current output of the system
+    r: f64,            // set point (reference input)
+    Ts: f64,           // sampling time
+    x: f64,            // current time
+    edit: Option<(LQRField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LQRField {
+    Qy,
+    Qi,
+    Ru,
+}
+
+const FIELDS: [LQRField; 3] = [LQRField::Qy, LQRField::Qi, LQRField::Ru];
+
+impl Field for LQRField {
+    fn label(self) -> &'static str {
+        match self {
+            LQRField::Qy => "q_y",
+            LQRField::Qi => "q_i",
+            LQRField::Ru => "r_u",
+        }
+    }
+}
+
+impl Default for LQRController {
+    fn default() -> Self {
+        LQRController::new(1.0, 0.5, 0.05, 0.1)
+    }
+}
+
+impl LQRController {
+    #[allow(non_snake_case)]
+    pub fn new(q_y: f64, q_i: f64, r_u: f64, Ts: f64) -> Self {
+        Self {
+            q_y,
+            q_i,
+            r_u,
+            model: None,
+            K: None,
+            x_p: Vec::new(),
+            z: 0.0,
+            y: 0.0,
+            r: 0.0,
+            Ts,
+            x: 0.0,
+            edit: None,
+        }
+    }
+
+    fn redesign(&mut self) {
+        self.K = self.model.as_ref().and_then(|model| {
+            let n = model.order();
+            let mut a = Matrix::identity(n + 1);
+            a.set_block(0, 0, &model.a);
+            a.set_block(n, 0, &model.c.scale(-self.Ts));
+            let mut b = Matrix::zeros(n + 1, 1);
+            b.set_block(0, 0, &model.b);
+            b.set_block(n, 0, &model.d.scale(-self.Ts));
+            let mut q = Matrix::zeros(n + 1, n + 1);
+            q.set_block(0, 0, &(&model.c.transpose() * &model.c).scale(self.q_y));
+            q[(n, n)] = self.q_i;
+            let r = Matrix::from_rows(&[&[self.r_u]]);
+            dlqr(&a, &b, &q, &r)
+        });
+    }
+
+    fn value(&self, field: LQRField) -> f64 {
+        match field {
+            LQRField::Qy => self.q_y,
+            LQRField::Qi => self.q_i,
+            LQRField::Ru => self.r_u,
+        }
+    }
+}
+
+impl FieldList for LQRController {
+    type Field = LQRField;
+
+    fn fields(&self) -> Vec<LQRField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: LQRField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: LQRField, value: f64) {
+        match field {
+            LQRField::Qy => self.q_y = value.max(0.0),
+            LQRField::Qi => self.q_i = value.max(0.0),
+            // a positive input weight keeps the Riccati equation well posed
+            LQRField::Ru => self.r_u = value.max(1e-6),
+        }
+        self.redesign();
+    }
+
+    fn field_edit(&self) -> Option<&(LQRField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(LQRField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Controller for LQRController {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(3)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_plant_output(&mut self, y: f64) {
+        self.y = y;
+    }
+    fn set_set_point(&mut self, r: f64) {
+        self.r = r;
+    }
+    fn set_plant_model(&mut self, model: Option<StateSpace>, x: Option<Vec<f64>>) {
+        if model != self.model {
+            self.model = model;
+            self.redesign();
+        }
+        self.x_p = x.unwrap_or_default();
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.z = 0.0;
+        self.y = 0.0;
+        self.r = 0.0;
+        self.x_p.clear();
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut (bool, Editing)) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+
+    fn uses_plant_model(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        CONTROLLER_NAME
+    }
+}
+
+impl Iterator for LQRController {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.x_p.len();
+        let controller_output = match self.K.as_ref() {
+            Some(k) if k.cols() == n + 1 => {
+                let feedback: f64 = self
+                    .x_p
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| k[(0, i)] * x)
+                    .sum();
+                -feedback - k[(0, n)] * self.z
+            }
+            _ => 0.0,
+        };
+        self.z += self.Ts * (self.r - self.y);
+        let point = (self.x, controller_output);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for LQRController {
+    type State = (bool, Editing);
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let editing = self.edit.is_some();
+        let mut lines = vec![
+            status_line(state.0, editing),
+            Line::from(Span::styled(
+                "LQR + integrator",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ];
+        lines.extend(self.field_lines());
+        let info_style = Style::default().gray().add_modifier(Modifier::BOLD);
+        match (self.model.as_ref(), self.K.as_ref()) {
+            (None, _) => lines.push(Line::from(Span::styled(
+                "no linear plant model",
+                info_style,
+            ))),
+            (Some(_), None) => lines.push(Line::from(Span::styled("Riccati diverged", info_style))),
+            (Some(_), Some(k)) => {
+                let gains = (0..k.cols())
+                    .map(|j| format!("{:.2}", k[(0, j)]))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                lines.push(Line::from(Span::styled(
+                    format!("K = [{}]", gains),
+                    info_style,
+                )));
+            }
+        }
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_controller!(LQRController, CONTROLLER_NAME);
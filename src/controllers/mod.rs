@@ -1,11 +1,22 @@
 use crossterm::event::KeyEvent;
+use ratatui::style::{Modifier, Stylize};
+use ratatui::text::{Line, Span};
 use ratatui::{layout::Rect, widgets::StatefulWidgetRef, Frame};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::Mutex;
 use crate::Editing;
+use crate::linalg::StateSpace;
 
 pub mod pid_0;
+pub mod pole_placement;
+pub mod fuzzy_pi;
+pub mod gain_scheduled;
+pub mod imc;
+pub mod lqr;
+pub mod mpc;
+pub mod relay;
+pub mod smith;
 
 #[macro_export]
 macro_rules! register_controller {
@@ -24,11 +35,22 @@ pub trait Controller: StatefulWidgetRef<State = (bool, Editing)> + Iterator<Item
 
     fn set_plant_output(&mut self, y: f64);
     fn set_set_point(&mut self, r: f64);
+    /// Passes the model and the current state of the controlled plant, see `Plant::state_space`.
+    /// Only model based controllers need them.
+    fn set_plant_model(&mut self, _model: Option<StateSpace>, _x: Option<Vec<f64>>) {}
+    /// Whether the controller is designed from the model of `set_plant_model`. Such a controller
+    /// cannot be the outer one of a cascade, whose process includes the closed inner loop.
+    fn uses_plant_model(&self) -> bool {
+        false
+    }
 
     fn set_edit(&mut self);
     fn reset(&mut self);
 
     fn render(&self, frame: &mut Frame, area: Rect, state: &mut (bool, Editing));
+    /// Renders an additional editor on top of the whole frame while `Editing::ControllerPopup`
+    /// is active. The popup is responsible for placing the cursor.
+    fn render_popup(&self, _frame: &mut Frame) {}
     fn name(&self) -> &'static str;
 }
 
@@ -47,3 +69,19 @@ pub fn get_controller_by_name(name: &str) -> Option<Box<dyn Controller + Send>>
 pub fn get_controller_by_index(idx: usize) -> Option<Box<dyn Controller + Send>> {
     CONTROLLER_REGISTRY.lock().unwrap().values().nth(idx).map(|f| f())
 }
+
+/// First line of a controller panel telling whether the controller is enabled.
+///
+/// The `<space>` hint is only shown while the panel is not being edited.
+pub fn status_line(active: bool, editing: bool) -> Line<'static> {
+    let status = if active {
+        Span::raw("ENABLED").green().add_modifier(Modifier::BOLD)
+    } else {
+        Span::raw("DISABLED").red().add_modifier(Modifier::BOLD)
+    };
+    if editing {
+        Line::from(status)
+    } else {
+        Line::from(vec![status, Span::raw(" <space>").white()])
+    }
+}
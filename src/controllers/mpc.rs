@@ -0,0 +1,348 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::controllers::{Controller, status_line};
+use crate::linalg::{Matrix, Observer, StateSpace, solve_qp};
+use crate::utils::{Field, FieldList, NumericInput};
+use crate::{Editing, register_controller};
+
+const CONTROLLER_NAME: &str = "Model Predictive Controller (MPC)";
+
+const MAX_HORIZON: f64 = 100.0;
+
+/// Linear model predictive controller with input and rate constraints.
+///
+/// Each tick the plant model predicts the output over `Np` samples as a function of the next `Nc`
+/// input moves Δu (the input is held after the control horizon) and the quadratic program
+///
+///   min sum q_y (y - r)^2 + sum r_du Δu^2
+///   s.t. u_min <= u <= u_max, |Δu| <= du_max
+///
+/// is solved for the moves, of which only the first is applied. The prediction starts from the
+/// state estimated by an observer from the measured output, and the disturbance it estimates at
+/// the plant input is kept constant over the horizon, which removes the steady state offset
+/// caused by load and output disturbances, feedforward or model mismatch.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct MPCController {
+    Np: usize,   // prediction horizon
+    Nc: usize,   // control horizon
+    q_y: f64,    // tracking error weight
+    r_du: f64,   // input move weight
+    u_min: f64,  // lower input limit
+    u_max: f64,  // upper input limit
+    du_max: f64, // largest input change per sample
+    model: Option<StateSpace>,
+    observer: Option<Observer>, // estimates the plant state from the measured output
+    G: Option<Matrix>,          // dynamic matrix, output response to the input moves
+    u: f64,                     // last controller output
+    active: Option<usize>,      // active constraints of the last QP, None if it failed
+    r: f64,                     // set point (reference input)
+    Ts: f64,                    // sampling time
+    x: f64,                     // current time
+    edit: Option<(MPCField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MPCField {
+    Np,
+    Nc,
+    Qy,
+    Rdu,
+    UMin,
+    UMax,
+    DuMax,
+}
+
+const FIELDS: [MPCField; 7] = [
+    MPCField::Np,
+    MPCField::Nc,
+    MPCField::Qy,
+    MPCField::Rdu,
+    MPCField::UMin,
+    MPCField::UMax,
+    MPCField::DuMax,
+];
+
+impl Field for MPCField {
+    fn label(self) -> &'static str {
+        match self {
+            MPCField::Np => "Np",
+            MPCField::Nc => "Nc",
+            MPCField::Qy => "q_y",
+            MPCField::Rdu => "r_du",
+            MPCField::UMin => "u_min",
+            MPCField::UMax => "u_max",
+            MPCField::DuMax => "du_max",
+        }
+    }
+}
+
+impl Default for MPCController {
+    fn default() -> Self {
+        MPCController::new(20, 5, 1.0, 0.1, -30.0, 30.0, 5.0, 0.1)
+    }
+}
+
+impl MPCController {
+    #[allow(non_snake_case)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        Np: usize,
+        Nc: usize,
+        q_y: f64,
+        r_du: f64,
+        u_min: f64,
+        u_max: f64,
+        du_max: f64,
+        Ts: f64,
+    ) -> Self {
+        Self {
+            Np,
+            Nc: Nc.min(Np),
+            q_y,
+            r_du,
+            u_min,
+            u_max,
+            du_max,
+            model: None,
+            G: None,
+            observer: None,
+            u: 0.0,
+            active: None,
+            r: 0.0,
+            Ts,
+            x: 0.0,
+            edit: None,
+        }
+    }
+
+    /// Output predicted over the prediction horizon from the state `x0`, the inputs `u` applied
+    /// from now on (the last one is held) and the constant state disturbance `w`.
+    fn predict(model: &StateSpace, x0: &[f64], u: &[f64], w: &[f64], horizon: usize) -> Vec<f64> {
+        let n = model.order();
+        let mut x = Matrix::column(x0);
+        let w = Matrix::column(w);
+        (0..horizon)
+            .map(|j| {
+                let u_j = u[j.min(u.len() - 1)];
+                x = &(&(&model.a * &x) + &model.b.scale(u_j)) + &w;
+                let u_next = u[(j + 1).min(u.len() - 1)];
+                (0..n).map(|i| model.c[(0, i)] * x[(i, 0)]).sum::<f64>() + model.d[(0, 0)] * u_next
+            })
+            .collect()
+    }
+
+    fn redesign(&mut self) {
+        self.G = self.model.as_ref().map(|model| {
+            let zeros = vec![0.0; model.order()];
+            let mut g = Matrix::zeros(self.Np, self.Nc);
+            for l in 0..self.Nc {
+                // unit move at sample l, the input stays at one afterwards
+                let u: Vec<f64> = (0..=self.Nc)
+                    .map(|i| if i >= l { 1.0 } else { 0.0 })
+                    .collect();
+                let response = Self::predict(model, &zeros, &u, &zeros, self.Np);
+                for (j, y) in response.into_iter().enumerate() {
+                    g[(j, l)] = y;
+                }
+            }
+            g
+        });
+    }
+
+    /// Solves the QP for the estimated state and returns the first input move.
+    fn solve(&mut self, model: &StateSpace, g: &Matrix, observer: &Observer) -> Option<f64> {
+        let n = model.order();
+        // the input disturbance acts on the states through B
+        let w: Vec<f64> = (0..n)
+            .map(|i| model.b[(i, 0)] * observer.disturbance())
+            .collect();
+        let free = Self::predict(model, &observer.state(), &[self.u], &w, self.Np);
+        let error = Matrix::column(&free.iter().map(|y| y - self.r).collect::<Vec<_>>());
+
+        let gt = g.transpose();
+        let h = &(&gt * g).scale(self.q_y) + &Matrix::identity(self.Nc).scale(self.r_du);
+        let f = (&gt * &error).scale(self.q_y);
+
+        // u = u_prev + L Δu with L lower triangular ones
+        let nc = self.Nc;
+        let mut m = Matrix::zeros(4 * nc, nc);
+        let mut limits = Vec::with_capacity(4 * nc);
+        for i in 0..nc {
+            for j in 0..=i {
+                m[(i, j)] = 1.0;
+                m[(nc + i, j)] = -1.0;
+            }
+            m[(2 * nc + i, i)] = 1.0;
+            m[(3 * nc + i, i)] = -1.0;
+        }
+        limits.extend(std::iter::repeat_n(self.u_max - self.u, nc));
+        limits.extend(std::iter::repeat_n(self.u - self.u_min, nc));
+        limits.extend(std::iter::repeat_n(self.du_max, 2 * nc));
+
+        let (du, active) = solve_qp(&h, &f, &m, &Matrix::column(&limits))?;
+        self.active = Some(active);
+        // the dual iteration stops at a tolerance, keep the applied input strictly feasible
+        let du = du[(0, 0)].clamp(-self.du_max, self.du_max);
+        let u = (self.u + du).clamp(self.u_min, self.u_max.max(self.u_min));
+        Some(u)
+    }
+
+    fn value(&self, field: MPCField) -> f64 {
+        match field {
+            MPCField::Np => self.Np as f64,
+            MPCField::Nc => self.Nc as f64,
+            MPCField::Qy => self.q_y,
+            MPCField::Rdu => self.r_du,
+            MPCField::UMin => self.u_min,
+            MPCField::UMax => self.u_max,
+            MPCField::DuMax => self.du_max,
+        }
+    }
+}
+
+impl FieldList for MPCController {
+    type Field = MPCField;
+
+    fn fields(&self) -> Vec<MPCField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: MPCField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: MPCField, value: f64) {
+        match field {
+            MPCField::Np => {
+                self.Np = value.round().clamp(1.0, MAX_HORIZON) as usize;
+                self.Nc = self.Nc.min(self.Np);
+            }
+            MPCField::Nc => self.Nc = (value.round().max(1.0) as usize).min(self.Np),
+            MPCField::Qy => self.q_y = value.max(0.0),
+            // a positive move weight keeps the QP strictly convex
+            MPCField::Rdu => self.r_du = value.max(1e-6),
+            MPCField::UMin => self.u_min = value,
+            MPCField::UMax => self.u_max = value,
+            MPCField::DuMax => self.du_max = value.abs(),
+        }
+        self.redesign();
+    }
+
+    fn field_edit(&self) -> Option<&(MPCField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(MPCField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Controller for MPCController {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(3)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_plant_output(&mut self, y: f64) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.update(self.u, y);
+        }
+    }
+    fn set_set_point(&mut self, r: f64) {
+        self.r = r;
+    }
+    fn set_plant_model(&mut self, model: Option<StateSpace>, _x: Option<Vec<f64>>) {
+        if model != self.model {
+            let mut observer = model.as_ref().and_then(Observer::new);
+            if let (Some(observer), Some(previous)) = (observer.as_mut(), self.observer.as_ref()) {
+                observer.carry_estimate(previous);
+            }
+            self.observer = observer;
+            self.model = model;
+            self.redesign();
+        }
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.u = 0.0;
+        self.r = 0.0;
+        if let Some(observer) = self.observer.as_mut() {
+            observer.reset();
+        }
+        self.active = None;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut (bool, Editing)) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+
+    fn uses_plant_model(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        CONTROLLER_NAME
+    }
+}
+
+impl Iterator for MPCController {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let solution = match (self.model.clone(), self.G.clone(), self.observer.clone()) {
+            (Some(model), Some(g), Some(observer)) => self.solve(&model, &g, &observer),
+            _ => None,
+        };
+        match solution {
+            Some(u) => self.u = u,
+            None => {
+                self.u = 0.0;
+                self.active = None;
+            }
+        }
+        let point = (self.x, self.u);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for MPCController {
+    type State = (bool, Editing);
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let editing = self.edit.is_some();
+        let mut lines = vec![
+            status_line(state.0, editing),
+            Line::from(Span::styled(
+                "Constrained MPC",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ];
+        lines.extend(self.field_lines());
+        let info_style = Style::default().gray().add_modifier(Modifier::BOLD);
+        let info = match (self.model.as_ref(), self.observer.as_ref(), self.active) {
+            (None, _, _) => "no linear plant model".to_string(),
+            (Some(_), None, _) => "observer diverged".to_string(),
+            (Some(_), Some(_), None) => "QP not solved".to_string(),
+            (Some(_), Some(observer), Some(active)) => {
+                format!("active = {} d = {:.2}", active, observer.disturbance())
+            }
+        };
+        lines.push(Line::from(Span::styled(info, info_style)));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_controller!(MPCController, CONTROLLER_NAME);
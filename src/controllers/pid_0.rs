@@ -82,6 +82,19 @@ impl PIDController {
         self.ku = (a1 / a0, a2 / a0);
         self.ke = (b0 / a0, b1 / a0, b2 / a0);
     }
+    /// Returns the (Kp, Ki, Kd, N) tuning of the controller.
+    pub fn gains(&self) -> (f64, f64, f64, f64) {
+        (self.Kp, self.Ki, self.Kd, self.N)
+    }
+    /// Sets the (Kp, Ki, Kd, N) tuning and recomputes the difference equation coefficients.
+    #[allow(non_snake_case)]
+    pub fn set_gains(&mut self, Kp: f64, Ki: f64, Kd: f64, N: f64) {
+        self.Kp = Kp;
+        self.Ki = Ki;
+        self.Kd = Kd;
+        self.N = N;
+        self.update_ku_ke_cooefficients();
+    }
     /// Reset the controller to the set point value which effectively disables the controller.
     pub fn reset_to_setpoint(&mut self, u: f64) {
         self.u.1 = 0.0;
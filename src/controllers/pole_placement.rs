@@ -0,0 +1,349 @@
+use std::collections::VecDeque;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::controllers::{Controller, status_line};
+use crate::linalg::{Matrix, StateSpace, poly_mul};
+use crate::utils::{Field, FieldList, NumericInput};
+use crate::{Editing, register_controller};
+
+const CONTROLLER_NAME: &str = "Pole Placement (RST)";
+
+/// Polynomial RST controller R(z) u = T(z) r - S(z) y designed by pole placement.
+///
+/// The closed-loop poles are the dominant pair given by the natural frequency `wn` and damping
+/// `zeta`, mapped with z = exp(s Ts), and all remaining poles at the auxiliary location `p_aux`.
+/// For the plant B(z) / A(z) of order n, R = (z - 1) R' contains an integrator and R' (degree
+/// n - 1) and S (degree n) solve the Diophantine equation
+///
+///   A (z - 1) R' + B S = P
+///
+/// through its Sylvester matrix. T = t0 z^(n-m) (z - p_aux)^m cancels up to m = min(n, 2n - 2)
+/// auxiliary poles from the reference response and t0 gives unit static gain.
+///
+/// The design uses the discrete model of the controlled plant and is redone whenever the model
+/// or the specification changes.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct PolePlacementController {
+    wn: f64,    // natural frequency of the dominant pair
+    zeta: f64,  // damping ratio of the dominant pair
+    p_aux: f64, // location of the auxiliary poles in the z plane
+    model: Option<StateSpace>,
+    rst: Option<Rst>,
+    u_hist: VecDeque<f64>, // u[k-1], u[k-2], ...
+    y_hist: VecDeque<f64>, // y[k], y[k-1], ...
+    r_hist: VecDeque<f64>, // r[k], r[k-1], ...
+    y: f64,                // current output of the system
+    r: f64,                // set point (reference input)
+    Ts: f64,               // sampling time
+    x: f64,                // current time
+    edit: Option<(PolePlacementField, NumericInput)>,
+}
+
+/// Controller polynomials in descending powers of z, all of the plant order with monic `r`.
+#[derive(Clone)]
+struct Rst {
+    r: Vec<f64>,
+    s: Vec<f64>,
+    t: Vec<f64>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PolePlacementField {
+    Wn,
+    Zeta,
+    Aux,
+}
+
+const FIELDS: [PolePlacementField; 3] = [
+    PolePlacementField::Wn,
+    PolePlacementField::Zeta,
+    PolePlacementField::Aux,
+];
+
+impl Field for PolePlacementField {
+    fn label(self) -> &'static str {
+        match self {
+            PolePlacementField::Wn => "wn",
+            PolePlacementField::Zeta => "zeta",
+            PolePlacementField::Aux => "p_aux",
+        }
+    }
+}
+
+impl Default for PolePlacementController {
+    fn default() -> Self {
+        PolePlacementController::new(1.0, 0.8, 0.3, 0.1)
+    }
+}
+
+impl PolePlacementController {
+    #[allow(non_snake_case)]
+    pub fn new(wn: f64, zeta: f64, p_aux: f64, Ts: f64) -> Self {
+        Self {
+            wn,
+            zeta,
+            p_aux,
+            model: None,
+            rst: None,
+            u_hist: VecDeque::new(),
+            y_hist: VecDeque::new(),
+            r_hist: VecDeque::new(),
+            y: 0.0,
+            r: 0.0,
+            Ts,
+            x: 0.0,
+            edit: None,
+        }
+    }
+
+    /// Characteristic polynomial z^2 + p1 z + p2 of the dominant pair.
+    fn dominant_pair(&self) -> Vec<f64> {
+        let (wn, zeta, ts) = (self.wn, self.zeta, self.Ts);
+        if zeta < 1.0 {
+            let decay = (-zeta * wn * ts).exp();
+            let wd = wn * (1.0 - zeta * zeta).sqrt();
+            vec![1.0, -2.0 * decay * (wd * ts).cos(), decay * decay]
+        } else {
+            let root = (zeta * zeta - 1.0).sqrt();
+            let z1 = (-wn * (zeta - root) * ts).exp();
+            let z2 = (-wn * (zeta + root) * ts).exp();
+            vec![1.0, -(z1 + z2), z1 * z2]
+        }
+    }
+
+    fn redesign(&mut self) {
+        self.rst = self.model.as_ref().and_then(|model| self.design(model));
+        // keep the past samples so that retuning a running loop is bumpless
+        let n = self.rst.as_ref().map_or(0, |rst| rst.r.len());
+        self.u_hist.resize(n, 0.0);
+        self.y_hist.resize(n, 0.0);
+        self.r_hist.resize(n, 0.0);
+    }
+
+    fn design(&self, model: &StateSpace) -> Option<Rst> {
+        let n = model.order();
+        if n == 0 {
+            return None;
+        }
+        let (b, a) = model.transfer_function();
+        let a_bar = poly_mul(&a, &[1.0, -1.0]);
+
+        // desired closed-loop polynomial of degree 2n
+        let mut p = self.dominant_pair();
+        for _ in 0..2 * n - 2 {
+            p = poly_mul(&p, &[1.0, -self.p_aux]);
+        }
+
+        // Sylvester system for R' = [r'_0 .. r'_(n-1)] and S = [s_0 .. s_n]
+        let size = 2 * n + 1;
+        let mut sylvester = Matrix::zeros(size, size);
+        for i in 0..n {
+            for (k, coefficient) in a_bar.iter().enumerate() {
+                sylvester[(i + k, i)] = *coefficient;
+            }
+        }
+        for j in 0..=n {
+            for (k, coefficient) in b.iter().enumerate() {
+                sylvester[(j + k, n + j)] = *coefficient;
+            }
+        }
+        let solution = &sylvester.inverse()? * &Matrix::column(&p);
+        let r_prime: Vec<f64> = (0..n).map(|i| solution[(i, 0)]).collect();
+        let s: Vec<f64> = (0..=n).map(|j| solution[(n + j, 0)]).collect();
+        let r = poly_mul(&r_prime, &[1.0, -1.0]);
+        let lead = r[0];
+        if lead.abs() < 1e-9 {
+            // the controller would not be causal
+            return None;
+        }
+
+        let m = n.min(2 * n - 2);
+        let mut t = vec![1.0];
+        for _ in 0..m {
+            t = poly_mul(&t, &[1.0, -self.p_aux]);
+        }
+        t.resize(n + 1, 0.0);
+        let gain = b.iter().sum::<f64>() * t.iter().sum::<f64>();
+        if gain.abs() < 1e-12 {
+            return None;
+        }
+        let t0 = p.iter().sum::<f64>() / gain;
+
+        Some(Rst {
+            r: r.iter().map(|c| c / lead).collect(),
+            s: s.iter().map(|c| c / lead).collect(),
+            t: t.iter().map(|c| c * t0 / lead).collect(),
+        })
+    }
+
+    fn value(&self, field: PolePlacementField) -> f64 {
+        match field {
+            PolePlacementField::Wn => self.wn,
+            PolePlacementField::Zeta => self.zeta,
+            PolePlacementField::Aux => self.p_aux,
+        }
+    }
+}
+
+impl FieldList for PolePlacementController {
+    type Field = PolePlacementField;
+
+    fn fields(&self) -> Vec<PolePlacementField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: PolePlacementField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: PolePlacementField, value: f64) {
+        match field {
+            PolePlacementField::Wn => self.wn = value.abs(),
+            PolePlacementField::Zeta => self.zeta = value.max(0.0),
+            // keep the auxiliary poles stable
+            PolePlacementField::Aux => self.p_aux = value.clamp(-0.99, 0.99),
+        }
+        self.redesign();
+    }
+
+    fn field_edit(&self) -> Option<&(PolePlacementField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(PolePlacementField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Controller for PolePlacementController {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(3)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_plant_output(&mut self, y: f64) {
+        self.y = y;
+    }
+    fn set_set_point(&mut self, r: f64) {
+        self.r = r;
+    }
+    fn set_plant_model(&mut self, model: Option<StateSpace>, _x: Option<Vec<f64>>) {
+        if model != self.model {
+            self.model = model;
+            self.redesign();
+        }
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.y = 0.0;
+        self.r = 0.0;
+        for value in self
+            .u_hist
+            .iter_mut()
+            .chain(self.y_hist.iter_mut())
+            .chain(self.r_hist.iter_mut())
+        {
+            *value = 0.0;
+        }
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut (bool, Editing)) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+
+    fn uses_plant_model(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        CONTROLLER_NAME
+    }
+}
+
+impl Iterator for PolePlacementController {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let controller_output = match self.rst.as_ref() {
+            Some(rst) => {
+                self.y_hist.push_front(self.y);
+                self.y_hist.truncate(rst.s.len());
+                self.r_hist.push_front(self.r);
+                self.r_hist.truncate(rst.t.len());
+                // u[k] = sum t_i r[k-i] - sum s_i y[k-i] - sum r_i u[k-i], i >= 1 for u
+                let reference: f64 = rst.t.iter().zip(&self.r_hist).map(|(t, r)| t * r).sum();
+                let feedback: f64 = rst.s.iter().zip(&self.y_hist).map(|(s, y)| s * y).sum();
+                let past: f64 = rst.r[1..]
+                    .iter()
+                    .zip(&self.u_hist)
+                    .map(|(r, u)| r * u)
+                    .sum();
+                let u = reference - feedback - past;
+                self.u_hist.push_front(u);
+                self.u_hist.truncate(rst.r.len() - 1);
+                u
+            }
+            None => 0.0,
+        };
+        let point = (self.x, controller_output);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+fn coefficients(name: &str, polynomial: &[f64]) -> String {
+    let values = polynomial
+        .iter()
+        .map(|c| format!("{:.2}", c))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("{} = [{}]", name, values)
+}
+
+impl StatefulWidgetRef for PolePlacementController {
+    type State = (bool, Editing);
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let editing = self.edit.is_some();
+        let mut lines = vec![
+            status_line(state.0, editing),
+            Line::from(Span::styled(
+                "Pole placement",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ];
+        lines.extend(self.field_lines());
+        let info_style = Style::default().gray().add_modifier(Modifier::BOLD);
+        match (self.model.as_ref(), self.rst.as_ref()) {
+            (None, _) => lines.push(Line::from(Span::styled(
+                "no linear plant model",
+                info_style,
+            ))),
+            (Some(_), None) => lines.push(Line::from(Span::styled("design failed", info_style))),
+            (Some(_), Some(rst)) => {
+                for (name, polynomial) in [("R", &rst.r), ("S", &rst.s), ("T", &rst.t)] {
+                    lines.push(Line::from(Span::styled(
+                        coefficients(name, polynomial),
+                        info_style,
+                    )));
+                }
+            }
+        }
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_controller!(PolePlacementController, CONTROLLER_NAME);
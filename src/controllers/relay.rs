@@ -0,0 +1,217 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::controllers::{status_line, Controller};
+use crate::utils::{Field, FieldList, NumericInput};
+use crate::{register_controller, Editing};
+
+const CONTROLLER_NAME: &str = "On/Off Relay with Hysteresis";
+
+/// On/off (bang-bang) controller with a hysteresis band, as found in thermostats.
+///
+/// The output switches to `u_on` once the error rises above half of the hysteresis band and back
+/// to `u_off` once it falls below minus half of the band. Optional minimum on and off times
+/// (0 disables them) keep the relay in its current position to limit the switching frequency.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct RelayController {
+    u_on: f64,       // output while switched on
+    u_off: f64,      // output while switched off
+    hysteresis: f64, // width of the hysteresis band around zero error
+    min_on: f64,     // minimum time to stay on after switching on
+    min_off: f64,    // minimum time to stay off after switching off
+    on: bool,        // current relay position
+    ticks: usize,    // samples spent in the current relay position
+    y: f64,          // current output of the system
+    r: f64,          // set point (reference input)
+    Ts: f64,         // sampling time
+    x: f64,          // current time
+    edit: Option<(RelayField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RelayField {
+    UOn,
+    UOff,
+    Hysteresis,
+    MinOn,
+    MinOff,
+}
+
+const FIELDS: [RelayField; 5] = [
+    RelayField::UOn,
+    RelayField::UOff,
+    RelayField::Hysteresis,
+    RelayField::MinOn,
+    RelayField::MinOff,
+];
+
+impl Field for RelayField {
+    fn label(self) -> &'static str {
+        match self {
+            RelayField::UOn => "u_on",
+            RelayField::UOff => "u_off",
+            RelayField::Hysteresis => "band",
+            RelayField::MinOn => "min on",
+            RelayField::MinOff => "min off",
+        }
+    }
+}
+
+impl Default for RelayController {
+    fn default() -> Self {
+        RelayController::new(20.0, 0.0, 2.0, 0.0, 0.0, 0.1)
+    }
+}
+
+impl RelayController {
+    #[allow(non_snake_case)]
+    pub fn new(u_on: f64, u_off: f64, hysteresis: f64, min_on: f64, min_off: f64, Ts: f64) -> Self {
+        Self {
+            u_on,
+            u_off,
+            hysteresis,
+            min_on,
+            min_off,
+            on: false,
+            ticks: 0,
+            y: 0.0,
+            r: 0.0,
+            Ts,
+            x: 0.0,
+            edit: None,
+        }
+    }
+
+    /// Number of samples covering the time `t`, counted instead of summing `Ts` so that rounding
+    /// errors do not add or drop a sample.
+    fn samples(&self, t: f64) -> usize {
+        (t / self.Ts).round() as usize
+    }
+
+    fn value(&self, field: RelayField) -> f64 {
+        match field {
+            RelayField::UOn => self.u_on,
+            RelayField::UOff => self.u_off,
+            RelayField::Hysteresis => self.hysteresis,
+            RelayField::MinOn => self.min_on,
+            RelayField::MinOff => self.min_off,
+        }
+    }
+}
+
+impl FieldList for RelayController {
+    type Field = RelayField;
+
+    fn fields(&self) -> Vec<RelayField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: RelayField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: RelayField, value: f64) {
+        match field {
+            RelayField::UOn => self.u_on = value,
+            RelayField::UOff => self.u_off = value,
+            RelayField::Hysteresis => self.hysteresis = value.abs(),
+            RelayField::MinOn => self.min_on = value.max(0.0),
+            RelayField::MinOff => self.min_off = value.max(0.0),
+        }
+    }
+
+    fn field_edit(&self) -> Option<&(RelayField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(RelayField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Controller for RelayController {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(3)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_plant_output(&mut self, y: f64) {
+        self.y = y;
+    }
+    fn set_set_point(&mut self, r: f64) {
+        self.r = r;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.on = false;
+        self.ticks = 0;
+        self.r = 0.0;
+        self.y = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut (bool, Editing)) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+
+    fn name(&self) -> &'static str {
+        CONTROLLER_NAME
+    }
+}
+
+impl Iterator for RelayController {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let e = self.r - self.y; // error = set point - plant_output
+        let half_band = 0.5 * self.hysteresis;
+        let (can_switch, should_switch) = if self.on {
+            (self.ticks >= self.samples(self.min_on), e < -half_band)
+        } else {
+            (self.ticks >= self.samples(self.min_off), e > half_band)
+        };
+        if can_switch && should_switch {
+            self.on = !self.on;
+            self.ticks = 0;
+        }
+        let controller_output = if self.on { self.u_on } else { self.u_off };
+        let point = (self.x, controller_output);
+        self.x += self.Ts;
+        self.ticks += 1;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for RelayController {
+    type State = (bool, Editing);
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let editing = self.edit.is_some();
+        let mut lines = vec![
+            status_line(state.0, editing),
+            Line::from(Span::styled(
+                "On/off relay",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ];
+        lines.extend(self.field_lines());
+        let position = if self.on { "ON" } else { "OFF" };
+        lines.push(Line::from(Span::styled(
+            format!("relay = {}", position),
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_controller!(RelayController, CONTROLLER_NAME);
@@ -0,0 +1,246 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::controllers::pid_0::PIDController;
+use crate::controllers::{status_line, Controller};
+use crate::utils::{DelayLine, Field, FieldList, NumericInput};
+use crate::{register_controller, Editing};
+
+const CONTROLLER_NAME: &str = "Smith Predictor (PID + FOPDT model)";
+
+/// Smith predictor for plants with dead time.
+///
+/// The internal PID acts on the output of an internal first-order-plus-dead-time model
+/// K_m / (tau_m s + 1) e^(-theta_m s) without the delay, corrected by the mismatch between the
+/// measured plant output and the delayed model output:
+///
+///   y_fb = y + y_m - y_m,delayed
+///
+/// With a perfect model the dead time is moved out of the loop. The model parameters are
+/// independent of the simulated plant so the effects of model mismatch can be studied.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct SmithPredictor {
+    pid: PIDController,
+    K_m: f64,      // model static gain
+    tau_m: f64,    // model time constant
+    theta_m: f64,  // model dead time
+    am: f64,       // model pole (zero order hold discretization)
+    bm: f64,       // model input coefficient
+    y_m: f64,      // model output without dead time
+    y_m_d: f64,    // model output with dead time
+    delay: DelayLine,
+    y: f64,        // current output of the system
+    r: f64,        // set point (reference input)
+    Ts: f64,       // sampling time
+    edit: Option<(SmithField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SmithField {
+    Kp,
+    Ki,
+    Kd,
+    N,
+    Gain,
+    Tau,
+    Theta,
+}
+
+const FIELDS: [SmithField; 7] = [
+    SmithField::Kp,
+    SmithField::Ki,
+    SmithField::Kd,
+    SmithField::N,
+    SmithField::Gain,
+    SmithField::Tau,
+    SmithField::Theta,
+];
+
+impl Field for SmithField {
+    fn label(self) -> &'static str {
+        match self {
+            SmithField::Kp => "Kp",
+            SmithField::Ki => "Ki",
+            SmithField::Kd => "Kd",
+            SmithField::N => "N",
+            SmithField::Gain => "K_m",
+            SmithField::Tau => "tau_m",
+            SmithField::Theta => "theta_m",
+        }
+    }
+}
+
+impl Default for SmithPredictor {
+    fn default() -> Self {
+        SmithPredictor::new(PIDController::new(0.5, 0.5, 0.0, 5.0, 0.1), 1.0, 2.0, 1.0, 0.1)
+    }
+}
+
+impl SmithPredictor {
+    #[allow(non_snake_case)]
+    pub fn new(pid: PIDController, K_m: f64, tau_m: f64, theta_m: f64, Ts: f64) -> Self {
+        let mut controller = Self {
+            pid,
+            K_m,
+            tau_m,
+            theta_m,
+            am: 0.0,
+            bm: 0.0,
+            y_m: 0.0,
+            y_m_d: 0.0,
+            delay: DelayLine::new(DelayLine::samples_for(theta_m, Ts)),
+            y: 0.0,
+            r: 0.0,
+            Ts,
+            edit: None,
+        };
+        controller.update_model();
+        controller
+    }
+
+    fn update_model(&mut self) {
+        self.am = if self.tau_m > 0.0 {
+            (-self.Ts / self.tau_m).exp()
+        } else {
+            0.0
+        };
+        self.bm = self.K_m * (1.0 - self.am);
+        self.delay
+            .resize(DelayLine::samples_for(self.theta_m, self.Ts));
+    }
+
+    fn value(&self, field: SmithField) -> f64 {
+        let (kp, ki, kd, n) = self.pid.gains();
+        match field {
+            SmithField::Kp => kp,
+            SmithField::Ki => ki,
+            SmithField::Kd => kd,
+            SmithField::N => n,
+            SmithField::Gain => self.K_m,
+            SmithField::Tau => self.tau_m,
+            SmithField::Theta => self.theta_m,
+        }
+    }
+}
+
+impl FieldList for SmithPredictor {
+    type Field = SmithField;
+
+    fn fields(&self) -> Vec<SmithField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: SmithField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: SmithField, value: f64) {
+        let (kp, ki, kd, n) = self.pid.gains();
+        match field {
+            SmithField::Kp => self.pid.set_gains(value, ki, kd, n),
+            SmithField::Ki => self.pid.set_gains(kp, value, kd, n),
+            SmithField::Kd => self.pid.set_gains(kp, ki, value, n),
+            SmithField::N => self.pid.set_gains(kp, ki, kd, value),
+            SmithField::Gain => self.K_m = value,
+            SmithField::Tau => self.tau_m = value.max(0.0),
+            SmithField::Theta => self.theta_m = value.max(0.0),
+        }
+        self.update_model();
+    }
+
+    fn field_edit(&self) -> Option<&(SmithField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(SmithField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Controller for SmithPredictor {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        let (x, y) = self.field_cursor(3);
+        // the model parameters are preceded by a "model" heading
+        match self.edit.as_ref() {
+            Some((field, _)) if FIELDS[4..].contains(field) => (x, y + 1),
+            _ => (x, y),
+        }
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_plant_output(&mut self, y: f64) {
+        self.y = y;
+    }
+    fn set_set_point(&mut self, r: f64) {
+        self.r = r;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.pid.reset();
+        self.delay.reset();
+        self.y_m = 0.0;
+        self.y_m_d = 0.0;
+        self.y = 0.0;
+        self.r = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut (bool, Editing)) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+
+    fn name(&self) -> &'static str {
+        CONTROLLER_NAME
+    }
+}
+
+impl Iterator for SmithPredictor {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        // y results from the previous input, as do the current model outputs
+        self.pid.set_set_point(self.r);
+        self.pid.set_plant_output(self.y + self.y_m - self.y_m_d);
+        let point = self.pid.next()?;
+
+        self.y_m = self.am * self.y_m + self.bm * point.1;
+        self.y_m_d = self.delay.push(self.y_m);
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for SmithPredictor {
+    type State = (bool, Editing);
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let editing = self.edit.is_some();
+        let mut lines = vec![
+            status_line(state.0, editing),
+            Line::from(Span::styled(
+                "Smith predictor",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ];
+        for (idx, field) in FIELDS.into_iter().enumerate() {
+            if idx == 4 {
+                lines.push(Line::from(Span::styled(
+                    "model:",
+                    Style::default().gray().add_modifier(Modifier::BOLD),
+                )));
+            }
+            lines.push(self.field_line(field));
+        }
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_controller!(SmithPredictor, CONTROLLER_NAME);
@@ -0,0 +1,610 @@
+use std::ops::{Add, Index, IndexMut, Mul, Sub};
+
+/// Small dense row-major matrix, sufficient for the low order models of the simulator.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            data: vec![0.0; rows * cols],
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut m = Self::zeros(n, n);
+        for i in 0..n {
+            m[(i, i)] = 1.0;
+        }
+        m
+    }
+
+    pub fn from_rows(rows: &[&[f64]]) -> Self {
+        let cols = rows.first().map_or(0, |r| r.len());
+        Self {
+            rows: rows.len(),
+            cols,
+            data: rows.iter().flat_map(|r| r.iter().copied()).collect(),
+        }
+    }
+
+    pub fn column(values: &[f64]) -> Self {
+        Self {
+            rows: values.len(),
+            cols: 1,
+            data: values.to_vec(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut t = Self::zeros(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                t[(j, i)] = self[(i, j)];
+            }
+        }
+        t
+    }
+
+    pub fn scale(&self, k: f64) -> Self {
+        Self {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.data.iter().map(|v| v * k).collect(),
+        }
+    }
+
+    /// Copies `other` into this matrix with its top left corner at (`row`, `col`).
+    pub fn set_block(&mut self, row: usize, col: usize, other: &Matrix) {
+        for i in 0..other.rows {
+            for j in 0..other.cols {
+                self[(row + i, col + j)] = other[(i, j)];
+            }
+        }
+    }
+
+    /// Largest absolute value of the entries.
+    pub fn max_abs(&self) -> f64 {
+        self.data.iter().fold(0.0, |m, v| m.max(v.abs()))
+    }
+
+    /// Inverse by Gauss-Jordan elimination with partial pivoting, `None` if singular.
+    pub fn inverse(&self) -> Option<Matrix> {
+        let n = self.rows;
+        let mut a = self.clone();
+        let mut inv = Matrix::identity(n);
+        for col in 0..n {
+            let pivot =
+                (col..n).max_by(|&i, &j| a[(i, col)].abs().total_cmp(&a[(j, col)].abs()))?;
+            if a[(pivot, col)].abs() < 1e-12 {
+                return None;
+            }
+            a.swap_rows(col, pivot);
+            inv.swap_rows(col, pivot);
+            let p = a[(col, col)];
+            for j in 0..n {
+                a[(col, j)] /= p;
+                inv[(col, j)] /= p;
+            }
+            for i in (0..n).filter(|i| *i != col) {
+                let f = a[(i, col)];
+                if f != 0.0 {
+                    for j in 0..n {
+                        a[(i, j)] -= f * a[(col, j)];
+                        inv[(i, j)] -= f * inv[(col, j)];
+                    }
+                }
+            }
+        }
+        Some(inv)
+    }
+
+    /// Matrix exponential by scaling and squaring of a truncated Taylor series.
+    pub fn expm(&self) -> Matrix {
+        let norm = (0..self.rows)
+            .map(|i| (0..self.cols).map(|j| self[(i, j)].abs()).sum::<f64>())
+            .fold(0.0, f64::max);
+        let squarings = if norm > 0.5 {
+            (norm / 0.5).log2().ceil() as i32
+        } else {
+            0
+        };
+        let a = self.scale(0.5f64.powi(squarings));
+        let mut term = Matrix::identity(self.rows);
+        let mut sum = term.clone();
+        for k in 1..=16 {
+            term = (&term * &a).scale(1.0 / k as f64);
+            sum = &sum + &term;
+        }
+        for _ in 0..squarings {
+            sum = &sum * &sum;
+        }
+        sum
+    }
+
+    fn swap_rows(&mut self, i: usize, j: usize) {
+        if i != j {
+            for c in 0..self.cols {
+                self.data.swap(i * self.cols + c, j * self.cols + c);
+            }
+        }
+    }
+}
+
+impl Index<(usize, usize)> for Matrix {
+    type Output = f64;
+    fn index(&self, (i, j): (usize, usize)) -> &f64 {
+        &self.data[i * self.cols + j]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut f64 {
+        &mut self.data[i * self.cols + j]
+    }
+}
+
+impl Add for &Matrix {
+    type Output = Matrix;
+    fn add(self, rhs: &Matrix) -> Matrix {
+        assert_eq!(
+            (self.rows, self.cols),
+            (rhs.rows, rhs.cols),
+            "matrix shapes differ"
+        );
+        Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data: self
+                .data
+                .iter()
+                .zip(&rhs.data)
+                .map(|(a, b)| a + b)
+                .collect(),
+        }
+    }
+}
+
+impl Sub for &Matrix {
+    type Output = Matrix;
+    fn sub(self, rhs: &Matrix) -> Matrix {
+        assert_eq!(
+            (self.rows, self.cols),
+            (rhs.rows, rhs.cols),
+            "matrix shapes differ"
+        );
+        Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data: self
+                .data
+                .iter()
+                .zip(&rhs.data)
+                .map(|(a, b)| a - b)
+                .collect(),
+        }
+    }
+}
+
+impl Mul for &Matrix {
+    type Output = Matrix;
+    fn mul(self, rhs: &Matrix) -> Matrix {
+        let mut m = Matrix::zeros(self.rows, rhs.cols);
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                let a = self[(i, k)];
+                if a != 0.0 {
+                    for j in 0..rhs.cols {
+                        m[(i, j)] += a * rhs[(k, j)];
+                    }
+                }
+            }
+        }
+        m
+    }
+}
+
+/// Discrete-time single input single output state-space model
+///
+///   x[k+1] = A x[k] + B u[k]
+///   y[k]   = C x[k] + D u[k]
+///
+/// Plants describe the path from the input they receive to the output the controller measures at
+/// the next tick, so `y[k]` is what `Controller::set_plant_output` reports when `x[k]` is the
+/// plant state.
+#[derive(Clone, PartialEq, Debug)]
+pub struct StateSpace {
+    pub a: Matrix,
+    pub b: Matrix,
+    pub c: Matrix,
+    pub d: Matrix,
+    pub ts: f64,
+}
+
+impl StateSpace {
+    pub fn order(&self) -> usize {
+        self.a.rows()
+    }
+
+    /// Transfer function B(z) / A(z) of the model as numerator and denominator coefficients in
+    /// descending powers of z, both of length `order() + 1` with a monic denominator.
+    ///
+    /// Uses the Faddeev-LeVerrier recursion adj(zI - A) = sum M_k z^(n-k) with M_1 = I and
+    /// M_(k+1) = A M_k + a_k I, a_k = -tr(A M_k) / k.
+    pub fn transfer_function(&self) -> (Vec<f64>, Vec<f64>) {
+        let n = self.order();
+        let d = self.d[(0, 0)];
+        let mut den = vec![1.0];
+        let mut num = vec![d];
+        let mut m = Matrix::identity(n);
+        for k in 1..=n {
+            let am = &self.a * &m;
+            let a_k = -(0..n).map(|i| am[(i, i)]).sum::<f64>() / k as f64;
+            let cmb = &(&self.c * &m) * &self.b;
+            den.push(a_k);
+            num.push(cmb[(0, 0)] + d * a_k);
+            m = &am + &Matrix::identity(n).scale(a_k);
+        }
+        (num, den)
+    }
+}
+
+/// Method used to turn a continuous-time model into a sampled one.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Discretization {
+    ZeroOrderHold,
+    Tustin,
+}
+
+/// Samples the continuous-time model dx/dt = A x + B u, y = C x + D u with period `ts`.
+///
+/// The zero order hold uses the exponential of the augmented matrix [A B; 0 0] ts, whose top
+/// blocks are e^(A ts) and the integral of e^(A t) B over one period. The Tustin (bilinear)
+/// transform substitutes s = 2 / ts (z - 1) / (z + 1) and does not exist when A has an
+/// eigenvalue at 2 / ts, in which case none is returned.
+pub fn discretize(
+    a: &Matrix,
+    b: &Matrix,
+    c: &Matrix,
+    d: &Matrix,
+    ts: f64,
+    method: Discretization,
+) -> Option<StateSpace> {
+    let n = a.rows();
+    match method {
+        Discretization::ZeroOrderHold => {
+            let mut augmented = Matrix::zeros(n + 1, n + 1);
+            augmented.set_block(0, 0, a);
+            augmented.set_block(0, n, b);
+            let e = augmented.scale(ts).expm();
+            let mut phi = Matrix::zeros(n, n);
+            let mut gamma = Matrix::zeros(n, 1);
+            for i in 0..n {
+                for j in 0..n {
+                    phi[(i, j)] = e[(i, j)];
+                }
+                gamma[(i, 0)] = e[(i, n)];
+            }
+            Some(StateSpace {
+                a: phi,
+                b: gamma,
+                c: c.clone(),
+                d: d.clone(),
+                ts,
+            })
+        }
+        Discretization::Tustin => {
+            let half = a.scale(ts / 2.0);
+            // I - A ts / 2 is singular only with a pole exactly at s = 2 / ts
+            let m = (&Matrix::identity(n) - &half).inverse()?;
+            let cm = c * &m;
+            Some(StateSpace {
+                a: &m * &(&Matrix::identity(n) + &half),
+                b: (&m * b).scale(ts),
+                d: d + &(&cm * b).scale(ts / 2.0),
+                c: cm,
+                ts,
+            })
+        }
+    }
+}
+
+/// Product of two polynomials given by their coefficients in descending powers.
+pub fn poly_mul(p: &[f64], q: &[f64]) -> Vec<f64> {
+    let mut product = vec![0.0; p.len() + q.len() - 1];
+    for (i, a) in p.iter().enumerate() {
+        for (j, b) in q.iter().enumerate() {
+            product[i + j] += a * b;
+        }
+    }
+    product
+}
+
+/// Solves the discrete algebraic Riccati equation
+///
+///   P = A'PA - A'PB (R + B'PB)^-1 B'PA + Q
+///
+/// by fixed point iteration, `None` if the iteration does not converge.
+pub fn dare(a: &Matrix, b: &Matrix, q: &Matrix, r: &Matrix) -> Option<Matrix> {
+    let at = a.transpose();
+    let bt = b.transpose();
+    let mut p = q.clone();
+    for _ in 0..10_000 {
+        let gain = (r + &(&(&bt * &p) * b)).inverse()?;
+        let k = &(&gain * &(&bt * &p)) * a;
+        let next = &(&(&(&at * &p) * a) - &(&(&(&at * &p) * b) * &k)) + q;
+        let delta = (&next - &p).max_abs();
+        p = next;
+        if !p.max_abs().is_finite() {
+            return None;
+        }
+        if delta <= 1e-10 * p.max_abs().max(1.0) {
+            return Some(p);
+        }
+    }
+    None
+}
+
+/// Optimal state feedback gain K = (R + B'PB)^-1 B'PA of the discrete linear quadratic
+/// regulator, P being the solution of the Riccati equation, `None` if it does not converge.
+pub fn dlqr(a: &Matrix, b: &Matrix, q: &Matrix, r: &Matrix) -> Option<Matrix> {
+    let p = dare(a, b, q, r)?;
+    let bt = b.transpose();
+    let gain = (r + &(&(&bt * &p) * b)).inverse()?;
+    Some(&(&gain * &(&bt * &p)) * a)
+}
+
+/// Variance of the process noise on the model states assumed by the observer.
+const STATE_NOISE: f64 = 1e-3;
+/// Variance of the changes of the input disturbance assumed by the observer.
+const DISTURBANCE_NOISE: f64 = 1e-2;
+/// Variance of the measurement noise assumed by the observer.
+const MEASUREMENT_NOISE: f64 = 1e-2;
+
+/// Steady state Kalman filter estimating the state of a plant model together with a constant
+/// disturbance d at its input from the measured output, with the augmented model
+///
+///   [x; d][k+1] = [A B; 0 1] [x; d][k] + [B; 0] u[k]
+///   y[k]        = C x[k] + D u[k]
+///
+/// The disturbance absorbs any constant mismatch between the model and the measurements, load
+/// and output disturbances or sensor bias alike, so a controller acting on the estimate has no
+/// steady state offset. Unlike an output disturbance it stays observable for integrating plants.
+#[derive(Clone)]
+pub struct Observer {
+    a: Matrix, // augmented model
+    b: Matrix,
+    c: Matrix,
+    d: f64,
+    gain: Matrix,     // filter gain L
+    estimate: Matrix, // [x; d] given the last measurement
+}
+
+impl Observer {
+    /// Designs the filter for `model`, `None` if its Riccati equation does not converge.
+    pub fn new(model: &StateSpace) -> Option<Self> {
+        let n = model.order();
+        let mut a = Matrix::identity(n + 1);
+        a.set_block(0, 0, &model.a);
+        a.set_block(0, n, &model.b);
+        let mut b = Matrix::zeros(n + 1, 1);
+        b.set_block(0, 0, &model.b);
+        let mut c = Matrix::zeros(1, n + 1);
+        c.set_block(0, 0, &model.c);
+        let mut q = Matrix::identity(n + 1).scale(STATE_NOISE);
+        q[(n, n)] = DISTURBANCE_NOISE;
+        let r = Matrix::from_rows(&[&[MEASUREMENT_NOISE]]);
+        // the filter Riccati equation is the regulator one of the dual system (A', C')
+        let p = dare(&a.transpose(), &c.transpose(), &q, &r)?;
+        let pct = &p * &c.transpose();
+        let innovation = (&(&c * &pct) + &r).inverse()?;
+        Some(Self {
+            gain: &pct * &innovation,
+            a,
+            b,
+            c,
+            d: model.d[(0, 0)],
+            estimate: Matrix::zeros(n + 1, 1),
+        })
+    }
+
+    /// Continues from the estimate of `previous` when it has the same order, so that following a
+    /// change of the model does not restart the estimation.
+    pub fn carry_estimate(&mut self, previous: &Observer) {
+        if previous.estimate.rows() == self.estimate.rows() {
+            self.estimate = previous.estimate.clone();
+        }
+    }
+
+    /// Estimated model state.
+    pub fn state(&self) -> Vec<f64> {
+        (0..self.estimate.rows() - 1)
+            .map(|i| self.estimate[(i, 0)])
+            .collect()
+    }
+
+    /// Estimated disturbance at the plant input.
+    pub fn disturbance(&self) -> f64 {
+        self.estimate[(self.estimate.rows() - 1, 0)]
+    }
+
+    /// Predicts the state reached with the input `u` applied since the last measurement and
+    /// corrects it with the new measured output `y`.
+    pub fn update(&mut self, u: f64, y: f64) {
+        let predicted = &(&self.a * &self.estimate) + &self.b.scale(u);
+        let error = y - (&self.c * &predicted)[(0, 0)] - self.d * u;
+        self.estimate = &predicted + &self.gain.scale(error);
+    }
+
+    pub fn reset(&mut self) {
+        self.estimate = Matrix::zeros(self.estimate.rows(), 1);
+    }
+}
+
+/// Solves the quadratic program
+///
+///   min 1/2 x'Hx + f'x  subject to  Mx <= g
+///
+/// for a positive definite `h` with Hildreth's dual coordinate ascent. The unconstrained optimum
+/// is returned directly when it is feasible. When the constraints are inconsistent the iteration
+/// stops after a fixed number of sweeps and returns its best approximation. The second value is
+/// the number of active constraints.
+pub fn solve_qp(h: &Matrix, f: &Matrix, m: &Matrix, g: &Matrix) -> Option<(Matrix, usize)> {
+    let h_inv = h.inverse()?;
+    let x = (&h_inv * f).scale(-1.0);
+    let mx = m * &x;
+    if (0..m.rows).all(|i| mx[(i, 0)] <= g[(i, 0)]) {
+        return Some((x, 0));
+    }
+    let mt = m.transpose();
+    let p = &(m * &h_inv) * &mt;
+    let d = g - &mx;
+    let mut lambda = vec![0.0; m.rows];
+    for _ in 0..500 {
+        let mut change = 0.0;
+        for i in 0..m.rows {
+            if p[(i, i)] <= 0.0 {
+                continue;
+            }
+            let w: f64 = (0..m.rows)
+                .filter(|j| *j != i)
+                .map(|j| p[(i, j)] * lambda[j])
+                .sum();
+            let next = (-(d[(i, 0)] + w) / p[(i, i)]).max(0.0);
+            change += (next - lambda[i]).powi(2);
+            lambda[i] = next;
+        }
+        if change < 1e-16 {
+            break;
+        }
+    }
+    let active = lambda.iter().filter(|l| **l > 0.0).count();
+    let correction = &(&h_inv * &mt) * &Matrix::column(&lambda);
+    Some((&x - &correction, active))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: f64 = 1e-9;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < TOLERANCE,
+            "{} differs from {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn expm_of_a_diagonal_matrix_exponentiates_the_diagonal() {
+        let e = Matrix::from_rows(&[&[1.0, 0.0], &[0.0, -2.0]]).expm();
+        assert_close(e[(0, 0)], 1f64.exp());
+        assert_close(e[(1, 1)], (-2f64).exp());
+        assert_close(e[(0, 1)], 0.0);
+        assert_close(e[(1, 0)], 0.0);
+    }
+
+    #[test]
+    fn zero_order_hold_of_a_first_order_lag() {
+        // 1 / (s + 1) sampled every ts: y[k+1] = e^(-ts) y[k] + (1 - e^(-ts)) u[k]
+        let ts = 0.1;
+        let one = Matrix::from_rows(&[&[1.0]]);
+        let model = discretize(
+            &Matrix::from_rows(&[&[-1.0]]),
+            &one,
+            &one,
+            &Matrix::zeros(1, 1),
+            ts,
+            Discretization::ZeroOrderHold,
+        )
+        .unwrap();
+        assert_close(model.a[(0, 0)], (-ts).exp());
+        assert_close(model.b[(0, 0)], 1.0 - (-ts).exp());
+    }
+
+    #[test]
+    fn tustin_transform_does_not_exist_with_a_pole_at_two_over_ts() {
+        let one = Matrix::from_rows(&[&[1.0]]);
+        let model = discretize(
+            &Matrix::from_rows(&[&[20.0]]),
+            &one,
+            &one,
+            &Matrix::zeros(1, 1),
+            0.1,
+            Discretization::Tustin,
+        );
+        assert!(model.is_none());
+    }
+
+    #[test]
+    fn dlqr_of_a_scalar_system_matches_the_closed_form() {
+        let (a, b, q, r): (f64, f64, f64, f64) = (1.2, 0.5, 1.0, 2.0);
+        // the scalar Riccati equation is b^2 p^2 + (r - a^2 r - b^2 q) p - q r = 0
+        let linear = r - a * a * r - b * b * q;
+        let p = (-linear + (linear * linear + 4.0 * b * b * q * r).sqrt()) / (2.0 * b * b);
+        let gain = dlqr(
+            &Matrix::from_rows(&[&[a]]),
+            &Matrix::from_rows(&[&[b]]),
+            &Matrix::from_rows(&[&[q]]),
+            &Matrix::from_rows(&[&[r]]),
+        )
+        .unwrap();
+        assert_close(gain[(0, 0)], b * p * a / (r + b * b * p));
+    }
+
+    #[test]
+    fn qp_with_an_active_constraint_lands_on_the_constraint() {
+        // min 1/2 (x1^2 + x2^2) - x1 - x2 subject to x1 + x2 <= 1
+        let (x, active) = solve_qp(
+            &Matrix::identity(2),
+            &Matrix::column(&[-1.0, -1.0]),
+            &Matrix::from_rows(&[&[1.0, 1.0]]),
+            &Matrix::column(&[1.0]),
+        )
+        .unwrap();
+        assert_eq!(active, 1);
+        assert_close(x[(0, 0)], 0.5);
+        assert_close(x[(1, 0)], 0.5);
+    }
+
+    #[test]
+    fn transfer_function_of_a_companion_matrix() {
+        let model = StateSpace {
+            a: Matrix::from_rows(&[&[0.0, 1.0], &[-2.0, -3.0]]),
+            b: Matrix::column(&[0.0, 1.0]),
+            c: Matrix::from_rows(&[&[1.0, 0.0]]),
+            d: Matrix::zeros(1, 1),
+            ts: 0.1,
+        };
+        let (num, den) = model.transfer_function();
+        for (actual, expected) in num.iter().zip([0.0, 0.0, 1.0]) {
+            assert_close(*actual, expected);
+        }
+        for (actual, expected) in den.iter().zip([1.0, 3.0, 2.0]) {
+            assert_close(*actual, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "matrix shapes differ")]
+    fn adding_matrices_of_different_shapes_panics() {
+        let _ = &Matrix::zeros(1, 2) + &Matrix::zeros(2, 1);
+    }
+}
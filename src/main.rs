@@ -10,17 +10,27 @@ use ratatui::widgets::{
 };
 use ratatui::{symbols, DefaultTerminal, Frame};
 mod utils;
+mod blocks;
 mod controllers;
 mod inputs;
+mod linalg;
 mod plants;
 pub use controllers::pid_0::PIDController;
 pub use inputs::step::StepSignal;
 pub use plants::first_order::FirstOrderSystem;
 
+use crate::blocks::actuator::Actuator;
+use crate::blocks::disturbance::{Disturbance, DisturbanceChannel, Injection};
+use crate::blocks::feedforward::Feedforward;
+use crate::blocks::guard::DivergenceGuard;
+use crate::blocks::schedule::ParameterSchedule;
+use crate::blocks::sensor::Sensor;
+use crate::blocks::setpoint::SetpointConditioner;
 use crate::controllers::{get_controller_by_index, Controller, CONTROLLER_REGISTRY};
 use crate::inputs::{get_reference_by_index, Reference, REFERENCE_REGISTRY};
 use crate::plants::second_order::SecondOrderSystem;
 use crate::plants::{PLANT_REGISTRY, Plant, get_plant_by_index};
+use crate::utils::cycle_index;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
@@ -32,6 +42,7 @@ struct App {
     reference_data: Vec<(f64, f64)>,
     plant: Box<dyn Plant>,
     plant_data: Vec<(f64, f64)>,
+    plant_state_data: Vec<(f64, f64)>, // state of the plant selected with `Plant::plotted_state`
     window: [f64; 2],
     samples_per_window: usize,
     sampling: f64,
@@ -40,6 +51,38 @@ struct App {
     simulation_on: bool,
     editing: Editing,
     is_controler_active: bool,
+    topology: Topology,
+    inner_plant: Box<dyn Plant>,
+    inner_plant_data: Vec<(f64, f64)>,
+    inner_controller: Box<dyn Controller>,
+    inner_controller_data: Vec<(f64, f64)>,
+    feedforward: Feedforward,
+    feedforward_data: Vec<(f64, f64)>,
+    setpoint: SetpointConditioner,
+    setpoint_data: Vec<(f64, f64)>,
+    actuator: Actuator,
+    command_data: Vec<(f64, f64)>,  // input commanded to the actuated plant
+    actuator_data: Vec<(f64, f64)>, // input applied by the actuator
+    sensor: Sensor,
+    measured_data: Vec<(f64, f64)>, // plant output as measured by the sensor
+    load_disturbance: f64, // measured disturbance entering at the input of the actuated plant
+    load: Disturbance,
+    load_data: Vec<(f64, f64)>,
+    output_disturbance: Disturbance,
+    output_disturbance_data: Vec<(f64, f64)>,
+    injection: Injection,
+    guard: DivergenceGuard,
+    schedule: ParameterSchedule, // changes of the outer plant parameters over time
+}
+
+/// Structure of the simulated control loop.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Topology {
+    /// reference -> controller -> plant
+    Single,
+    /// reference -> outer controller -> inner controller -> inner plant -> outer plant, where the
+    /// outer controller output is the set point of the inner controller
+    Cascade,
 }
 
 #[derive(Clone)]
@@ -48,9 +91,90 @@ pub enum Editing {
     Reference,
     ReferenceType(Option<usize>),
     Plant,
+    PlantPopup,
     PlantType(Option<usize>),
     Controller,
+    ControllerPopup,
     ControllerType(Option<usize>),
+    InnerPlant,
+    InnerPlantPopup,
+    InnerPlantType(Option<usize>),
+    InnerController,
+    InnerControllerPopup,
+    InnerControllerType(Option<usize>),
+    Feedforward,
+    Setpoint,
+    Actuator,
+    Sensor,
+    Guard,
+    Disturbance(DisturbanceChannel),
+    DisturbanceType(DisturbanceChannel, Option<usize>),
+    Injection,
+    Schedule,
+}
+
+impl Editing {
+    /// Components only know the editing states of the single loop, so the inner loop states of
+    /// the cascade are translated before handing them over.
+    fn to_single_loop(&self) -> Editing {
+        match self {
+            Editing::InnerPlant => Editing::Plant,
+            Editing::InnerPlantPopup => Editing::PlantPopup,
+            Editing::InnerController => Editing::Controller,
+            Editing::InnerControllerPopup => Editing::ControllerPopup,
+            other => other.clone(),
+        }
+    }
+
+    /// Inverse of `to_single_loop`, applied once the component is done with the state.
+    fn to_inner_loop(&self) -> Editing {
+        match self {
+            Editing::Plant => Editing::InnerPlant,
+            Editing::PlantPopup => Editing::InnerPlantPopup,
+            Editing::Controller => Editing::InnerController,
+            Editing::ControllerPopup => Editing::InnerControllerPopup,
+            other => other.clone(),
+        }
+    }
+}
+
+/// Signals of the loop recorded for the charts.
+#[derive(Clone, Copy)]
+enum Series {
+    Reference,
+    Setpoint,
+    Load,
+    OutputDisturbance,
+    Feedforward,
+    Controller,
+    InnerController,
+    Command,
+    Applied,
+    InnerPlant,
+    Plant,
+    PlantState,
+    Measured,
+}
+
+impl Series {
+    /// Name reported by the divergence guard.
+    fn name(self) -> &'static str {
+        match self {
+            Series::Reference => "reference",
+            Series::Setpoint => "setpoint",
+            Series::Load => "load disturbance",
+            Series::OutputDisturbance => "output disturbance",
+            Series::Feedforward => "feedforward",
+            Series::Controller => "controller",
+            Series::InnerController => "inner controller",
+            Series::Command => "command",
+            Series::Applied => "actuator",
+            Series::InnerPlant => "inner plant",
+            Series::Plant => "plant",
+            Series::PlantState => "plant state",
+            Series::Measured => "measured output",
+        }
+    }
 }
 
 const WINDOW_SIZE: f64 = 20.0;
@@ -64,11 +188,14 @@ impl App {
         let input_data = input.by_ref().take(0).collect::<Vec<(f64, f64)>>();
         let output_data = plant.by_ref().take(0).collect::<Vec<(f64, f64)>>();
         let controller_data = controller.by_ref().take(0).collect::<Vec<(f64, f64)>>();
+        let inner_plant = Box::new(FirstOrderSystem::new(sampling, 0.8, 0.2, None));
+        let inner_controller = Box::new(PIDController::new(1.0, 2.0, 0.0, 5.0, sampling));
         Self {
             reference: input,
             reference_data: input_data,
             plant,
             plant_data: output_data,
+            plant_state_data: Vec::new(),
             window: [0.0, WINDOW_SIZE],
             samples_per_window,
             sampling,
@@ -77,20 +204,63 @@ impl App {
             editing: Editing::None,
             controller_data: controller_data,
             is_controler_active: true,
+            topology: Topology::Single,
+            inner_plant,
+            inner_plant_data: Vec::new(),
+            inner_controller,
+            inner_controller_data: Vec::new(),
+            feedforward: Feedforward::new(sampling),
+            feedforward_data: Vec::new(),
+            setpoint: SetpointConditioner::new(sampling),
+            setpoint_data: Vec::new(),
+            actuator: Actuator::new(sampling),
+            command_data: Vec::new(),
+            actuator_data: Vec::new(),
+            sensor: Sensor::new(sampling),
+            measured_data: Vec::new(),
+            load_disturbance: 0.0,
+            load: Disturbance::new(),
+            load_data: Vec::new(),
+            output_disturbance: Disturbance::new(),
+            output_disturbance_data: Vec::new(),
+            injection: Injection::default(),
+            guard: DivergenceGuard::default(),
+            schedule: ParameterSchedule::default(),
         }
     }
 
     fn reset(&mut self) {
         self.reference.reset();
+        self.schedule.reset(self.plant.as_mut());
         self.plant.reset();
         self.controller.reset();
         self.reference_data = self.reference.by_ref().take(0).collect::<Vec<(f64, f64)>>();
         self.plant_data = self.plant.by_ref().take(0).collect::<Vec<(f64, f64)>>();
+        self.plant_state_data.clear();
         self.controller_data = self
             .controller
             .by_ref()
             .take(0)
             .collect::<Vec<(f64, f64)>>();
+        self.inner_plant.reset();
+        self.inner_controller.reset();
+        self.inner_plant_data.clear();
+        self.inner_controller_data.clear();
+        self.feedforward.reset();
+        self.feedforward_data.clear();
+        self.setpoint.reset();
+        self.setpoint_data.clear();
+        self.actuator.reset();
+        self.command_data.clear();
+        self.actuator_data.clear();
+        self.sensor.reset();
+        self.measured_data.clear();
+        self.load.reset();
+        self.load_data.clear();
+        self.load_disturbance = 0.0;
+        self.output_disturbance.reset();
+        self.output_disturbance_data.clear();
+        self.guard.reset();
         self.window = [0.0, WINDOW_SIZE];
     }
 
@@ -124,6 +294,10 @@ impl App {
                 Editing::None => match k.code {
                     KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(true),
                     KeyCode::Char('s') | KeyCode::Char('S') => {
+                        // a diverged simulation starts over
+                        if self.guard.tripped().is_some() {
+                            self.reset();
+                        }
                         self.simulation_on = !self.simulation_on;
                     }
                     KeyCode::Char('i') => {
@@ -150,15 +324,227 @@ impl App {
                     KeyCode::Char('C') => {
                         self.editing = Editing::ControllerType(None);
                     }
+                    KeyCode::Char('t') | KeyCode::Char('T') => {
+                        self.topology = match self.topology {
+                            // a model based controller cannot close the outer loop
+                            Topology::Single if self.controller.uses_plant_model() => return Ok(false),
+                            Topology::Single => Topology::Cascade,
+                            Topology::Cascade => Topology::Single,
+                        };
+                        self.reset();
+                    }
+                    KeyCode::Char('j') if self.topology == Topology::Cascade => {
+                        self.editing = Editing::InnerPlant;
+                        self.inner_plant.set_edit();
+                    }
+                    KeyCode::Char('J') if self.topology == Topology::Cascade => {
+                        self.editing = Editing::InnerPlantType(None);
+                    }
+                    KeyCode::Char('k') if self.topology == Topology::Cascade => {
+                        self.editing = Editing::InnerController;
+                        self.inner_controller.set_edit();
+                    }
+                    KeyCode::Char('K') if self.topology == Topology::Cascade => {
+                        self.editing = Editing::InnerControllerType(None);
+                    }
+                    KeyCode::Char('f') | KeyCode::Char('F') => {
+                        self.editing = Editing::Feedforward;
+                        self.feedforward.set_edit();
+                    }
+                    KeyCode::Char('r') | KeyCode::Char('R') => {
+                        self.editing = Editing::Setpoint;
+                        self.setpoint.set_edit();
+                    }
+                    KeyCode::Char('a') | KeyCode::Char('A') => {
+                        self.editing = Editing::Actuator;
+                        self.actuator.set_edit();
+                    }
+                    KeyCode::Char('m') | KeyCode::Char('M') => {
+                        self.editing = Editing::Sensor;
+                        self.sensor.set_edit();
+                    }
+                    KeyCode::Char('g') | KeyCode::Char('G') => {
+                        self.editing = Editing::Guard;
+                        self.guard.set_edit();
+                    }
+                    KeyCode::Char(c @ ('l' | 'o')) => {
+                        let (channel, disturbance) = match c {
+                            'l' => (DisturbanceChannel::Load, &mut self.load),
+                            _ => (DisturbanceChannel::Output, &mut self.output_disturbance),
+                        };
+                        // without a generator there is nothing to edit but its type
+                        self.editing = match disturbance.generator_name() {
+                            Some(_) => {
+                                disturbance.set_edit();
+                                Editing::Disturbance(channel)
+                            }
+                            None => Editing::DisturbanceType(channel, None),
+                        };
+                    }
+                    KeyCode::Char('L') => {
+                        self.editing = Editing::DisturbanceType(DisturbanceChannel::Load, None);
+                    }
+                    KeyCode::Char('O') => {
+                        self.editing = Editing::DisturbanceType(DisturbanceChannel::Output, None);
+                    }
+                    KeyCode::Char('d') | KeyCode::Char('D') => {
+                        self.editing = Editing::Injection;
+                        self.injection.set_edit();
+                    }
+                    KeyCode::Char('e') | KeyCode::Char('E') => {
+                        self.editing = Editing::Schedule;
+                        self.schedule.set_edit();
+                    }
+                    KeyCode::Char(c @ ('x' | 'X')) => {
+                        let disturbance = match self.injection.target() {
+                            DisturbanceChannel::Load => &mut self.load,
+                            DisturbanceChannel::Output => &mut self.output_disturbance,
+                        };
+                        if c == 'x' {
+                            disturbance.inject_step(self.injection.amplitude());
+                        } else {
+                            disturbance.inject_impulse(self.injection.amplitude());
+                        }
+                    }
                     _ => (),
                 },
+                Editing::Feedforward => {
+                    self.feedforward.edit(&mut self.editing, k);
+                }
+                Editing::Setpoint => {
+                    self.setpoint.edit(&mut self.editing, k);
+                }
+                Editing::Actuator => {
+                    self.actuator.edit(&mut self.editing, k);
+                }
+                Editing::Sensor => {
+                    self.sensor.edit(&mut self.editing, k);
+                }
+                Editing::Guard => {
+                    self.guard.edit(&mut self.editing, k);
+                }
+                Editing::Injection => {
+                    self.injection.edit(&mut self.editing, k);
+                }
+                Editing::Schedule => {
+                    let parameters = self.plant.parameters();
+                    self.schedule.edit(&mut self.editing, k, &parameters);
+                }
+                Editing::Disturbance(channel) => match channel {
+                    DisturbanceChannel::Load => self.load.edit(&mut self.editing, k),
+                    DisturbanceChannel::Output => {
+                        self.output_disturbance.edit(&mut self.editing, k)
+                    }
+                },
+                Editing::DisturbanceType(channel, idx) => match k.code {
+                    KeyCode::Esc => {
+                        self.editing = Editing::None;
+                    }
+                    KeyCode::Down | KeyCode::Up => {
+                        // the first entry turns the generator off
+                        let count = REFERENCE_REGISTRY.lock().unwrap().len() + 1;
+                        let idx = step_selection(idx, count, k.code == KeyCode::Down);
+                        self.editing = Editing::DisturbanceType(channel, Some(idx));
+                    }
+                    KeyCode::Enter => {
+                        if let Some(selected_idx) = idx {
+                            let disturbance = match channel {
+                                DisturbanceChannel::Load => &mut self.load,
+                                DisturbanceChannel::Output => &mut self.output_disturbance,
+                            };
+                            let current_idx = disturbance.generator_name().map_or(0, |current| {
+                                REFERENCE_REGISTRY
+                                    .lock()
+                                    .unwrap()
+                                    .keys()
+                                    .position(|n| *n == current)
+                                    .map_or(0, |i| i + 1)
+                            });
+                            if current_idx != selected_idx {
+                                disturbance.set_generator(
+                                    selected_idx
+                                        .checked_sub(1)
+                                        .and_then(get_reference_by_index),
+                                );
+                                self.reset();
+                            }
+                        }
+                        self.editing = Editing::None;
+                    }
+                    _ => {}
+                },
+                Editing::InnerPlant | Editing::InnerPlantPopup => {
+                    let mut editing = self.editing.to_single_loop();
+                    self.inner_plant.edit(&mut editing, k);
+                    self.editing = editing.to_inner_loop();
+                }
+                Editing::InnerController | Editing::InnerControllerPopup => {
+                    let mut editing = self.editing.to_single_loop();
+                    self.inner_controller.edit(&mut editing, k);
+                    self.editing = editing.to_inner_loop();
+                }
+                Editing::InnerPlantType(idx) => match k.code {
+                    KeyCode::Esc => {
+                        self.editing = Editing::None;
+                    }
+                    KeyCode::Down | KeyCode::Up => {
+                        let count = PLANT_REGISTRY.lock().unwrap().len();
+                        let idx = step_selection(idx, count, k.code == KeyCode::Down);
+                        self.editing = Editing::InnerPlantType(Some(idx));
+                    }
+                    KeyCode::Enter => {
+                        if let Some(selected_idx) = idx {
+                            let current = self.inner_plant.name();
+                            let current_idx = PLANT_REGISTRY
+                                .lock()
+                                .unwrap()
+                                .keys()
+                                .position(|n| *n == current)
+                                .unwrap_or(0);
+                            if current_idx != selected_idx {
+                                self.inner_plant = get_plant_by_index(selected_idx).unwrap();
+                                self.reset();
+                            }
+                        }
+                        self.editing = Editing::None;
+                    }
+                    _ => {}
+                },
+                Editing::InnerControllerType(idx) => match k.code {
+                    KeyCode::Esc => {
+                        self.editing = Editing::None;
+                    }
+                    KeyCode::Down | KeyCode::Up => {
+                        let count = CONTROLLER_REGISTRY.lock().unwrap().len();
+                        let idx = step_selection(idx, count, k.code == KeyCode::Down);
+                        self.editing = Editing::InnerControllerType(Some(idx));
+                    }
+                    KeyCode::Enter => {
+                        if let Some(selected_idx) = idx {
+                            let current = self.inner_controller.name();
+                            let current_idx = CONTROLLER_REGISTRY
+                                .lock()
+                                .unwrap()
+                                .keys()
+                                .position(|n| *n == current)
+                                .unwrap_or(0);
+                            if current_idx != selected_idx {
+                                self.inner_controller =
+                                    get_controller_by_index(selected_idx).unwrap();
+                                self.reset();
+                            }
+                        }
+                        self.editing = Editing::None;
+                    }
+                    _ => {}
+                },
                 Editing::Reference => {
                     self.reference.edit(&mut self.editing, k);
                 }
-                Editing::Plant => {
+                Editing::Plant | Editing::PlantPopup => {
                     self.plant.edit(&mut self.editing, k);
                 }
-                Editing::Controller => {
+                Editing::Controller | Editing::ControllerPopup => {
                     self.controller.edit(&mut self.editing, k);
                 }
                 Editing::ReferenceType(idx) => match k.code {
@@ -245,6 +631,8 @@ impl App {
                                 .position(|n| *n == current)
                                 .unwrap_or(0);
                             if current_idx != selected_idx {
+                                self.schedule.reset(self.plant.as_mut());
+                                self.schedule.clear();
                                 self.plant = get_plant_by_index(selected_idx).unwrap();
                                 self.reset();
                             }
@@ -290,8 +678,13 @@ impl App {
                                 .keys()
                                 .position(|n| *n == current)
                                 .unwrap_or(0);
+                            let controller = get_controller_by_index(selected_idx).unwrap();
+                            // the outer loop of a cascade has no plant model to design from
+                            if self.topology == Topology::Cascade && controller.uses_plant_model() {
+                                return Ok(false);
+                            }
                             if current_idx != selected_idx {
-                                self.controller = get_controller_by_index(selected_idx).unwrap();
+                                self.controller = controller;
                                 self.reset();
                             }
                         }
@@ -305,67 +698,187 @@ impl App {
     }
 
     fn on_tick(&mut self) {
-        if self.reference_data.len() >= self.samples_per_window {
-            self.reference_data.drain(0..1);
+        let capacity = self.samples_per_window;
+        // samples of this tick, recorded only once they all passed the divergence guard
+        let mut samples = Vec::new();
+        let Some((x, reference)) = self.reference.next() else {
+            return;
+        };
+        samples.push((Series::Reference, (x, reference)));
+        let set_point = self.setpoint.step(reference);
+        if self.setpoint.is_enabled() {
+            samples.push((Series::Setpoint, (x, set_point)));
         }
-        self.reference_data.extend(self.reference.by_ref().take(1));
+        self.schedule.apply(self.plant.as_mut(), x);
 
-        if self.is_controler_active {
-            self.controller
-                .set_set_point(self.reference_data.last().map_or(0.0, |(_, y)| *y));
-            if self.controller_data.len() >= self.samples_per_window {
-                self.controller_data.drain(0..1);
-            }
-            self.controller_data
-                .extend(self.controller.by_ref().take(1));
+        self.load_disturbance = self.load.step();
+        let output_disturbance = self.output_disturbance.step();
+        if self.load.is_active() {
+            samples.push((Series::Load, (x, self.load_disturbance)));
+        }
+        if self.output_disturbance.is_active() {
+            samples.push((Series::OutputDisturbance, (x, output_disturbance)));
+        }
 
-            self.plant
-                .set_input(self.controller_data.last().map_or(0.0, |(_, y)| *y));
-            if self.plant_data.len() >= self.samples_per_window {
-                self.plant_data.drain(0..1);
+        let plant_input = if self.is_controler_active {
+            // the feedforward acts on the plant driven by a controller, the inner one in cascade
+            let measured_load = self.load.is_active().then_some(self.load_disturbance);
+            let u_ff = self.feedforward.step(set_point, measured_load);
+            if self.feedforward.is_enabled() {
+                samples.push((Series::Feedforward, (x, u_ff)));
+            }
+            self.controller.set_set_point(set_point);
+            if self.topology == Topology::Single {
+                self.controller
+                    .set_plant_model(self.plant.state_space(), self.plant.state());
+            }
+            let controller_sample = self.controller.next().unwrap_or_default();
+            samples.push((Series::Controller, controller_sample));
+            let controller_output = controller_sample.1;
+            match self.topology {
+                Topology::Single => {
+                    self.actuate(controller_output + u_ff, x, &mut samples) + self.load_disturbance
+                }
+                Topology::Cascade => {
+                    // the outer controller output is the set point of the inner loop
+                    self.inner_controller.set_set_point(controller_output);
+                    self.inner_controller
+                        .set_plant_model(self.inner_plant.state_space(), self.inner_plant.state());
+                    let inner_sample = self.inner_controller.next().unwrap_or_default();
+                    samples.push((Series::InnerController, inner_sample));
+                    let applied = self.actuate(inner_sample.1 + u_ff, x, &mut samples);
+                    let inner_output =
+                        self.step_inner_plant(applied + self.load_disturbance, &mut samples);
+                    self.inner_controller.set_plant_output(inner_output);
+                    inner_output
+                }
             }
-            self.plant_data.extend(self.plant.by_ref().take(1));
-
-            self.controller
-                .set_plant_output(self.plant_data.last().map_or(0.0, |(_, y)| *y));
         } else {
-            let set_point = self.reference_data.last().map_or(0.0, |(_, y)| *y);
             // self.controller.reset_to_setpoint(set_point);
-            if self.controller_data.len() >= self.samples_per_window {
-                self.controller_data.drain(0..1);
-            }
-            let last_controller_output = self.controller_data.last().map_or(0.0, |(_, y)| *y);
-            let x = self
-                .controller
-                .by_ref()
-                .take(1)
-                .next()
-                .unwrap_or((0.0, 0.0))
-                .0;
-            self.controller_data.push((x, last_controller_output));
-
-            self.plant.set_input(set_point);
-            if self.plant_data.len() >= self.samples_per_window {
-                self.plant_data.drain(0..1);
+            samples.push((
+                Series::Controller,
+                hold_output(self.controller.as_mut(), &self.controller_data),
+            ));
+            match self.topology {
+                Topology::Single => self.actuate(set_point, x, &mut samples) + self.load_disturbance,
+                Topology::Cascade => {
+                    samples.push((
+                        Series::InnerController,
+                        hold_output(self.inner_controller.as_mut(), &self.inner_controller_data),
+                    ));
+                    let applied = self.actuate(set_point, x, &mut samples);
+                    self.step_inner_plant(applied + self.load_disturbance, &mut samples)
+                }
             }
-            self.plant_data.extend(self.plant.by_ref().take(1));
         };
+
+        self.plant.set_input(plant_input);
+        let (x, y) = self
+            .plant
+            .next()
+            .map(|(x, y)| (x, y + output_disturbance))
+            .unwrap_or_default();
+        samples.push((Series::Plant, (x, y)));
+        let plotted_state = self.plant.plotted_state();
+        if let Some((_, value)) = plotted_state {
+            samples.push((Series::PlantState, (x, value)));
+        }
+        let measured = self.sensor.step(y);
+        if self.sensor.is_enabled() {
+            samples.push((Series::Measured, (x, measured)));
+        }
+        if self.is_controler_active {
+            self.controller.set_plant_output(measured);
+        }
+
+        // a diverged tick freezes the simulation before reaching the charts
+        for (series, (_, value)) in &samples {
+            if self.guard.check(series.name(), *value) {
+                self.simulation_on = false;
+                return;
+            }
+        }
+        if plotted_state.is_none() {
+            self.plant_state_data.clear();
+        }
+        for (series, point) in samples {
+            push_sample(self.series_data(series), Some(point), capacity);
+        }
+
         if self.plant_data.len() >= self.samples_per_window {
             self.window[0] += self.sampling;
             self.window[1] += self.sampling;
         }
     }
 
+    /// Recorded samples of a signal of the loop.
+    fn series_data(&mut self, series: Series) -> &mut Vec<(f64, f64)> {
+        match series {
+            Series::Reference => &mut self.reference_data,
+            Series::Setpoint => &mut self.setpoint_data,
+            Series::Load => &mut self.load_data,
+            Series::OutputDisturbance => &mut self.output_disturbance_data,
+            Series::Feedforward => &mut self.feedforward_data,
+            Series::Controller => &mut self.controller_data,
+            Series::InnerController => &mut self.inner_controller_data,
+            Series::Command => &mut self.command_data,
+            Series::Applied => &mut self.actuator_data,
+            Series::InnerPlant => &mut self.inner_plant_data,
+            Series::Plant => &mut self.plant_data,
+            Series::PlantState => &mut self.plant_state_data,
+            Series::Measured => &mut self.measured_data,
+        }
+    }
+
+    /// Passes the input commanded to the actuated plant, the inner one in cascade, through the
+    /// actuator and returns the input it applies.
+    fn actuate(&mut self, u: f64, x: f64, samples: &mut Vec<(Series, (f64, f64))>) -> f64 {
+        let applied = self.actuator.step(u);
+        if self.actuator.is_enabled() {
+            samples.push((Series::Command, (x, u)));
+            samples.push((Series::Applied, (x, applied)));
+        }
+        applied
+    }
+
+    /// Feeds the inner plant of the cascade and returns its new output.
+    fn step_inner_plant(&mut self, u: f64, samples: &mut Vec<(Series, (f64, f64))>) -> f64 {
+        self.inner_plant.set_input(u);
+        let sample = self.inner_plant.next().unwrap_or_default();
+        samples.push((Series::InnerPlant, sample));
+        sample.1
+    }
+
     fn render(&mut self, frame: &mut Frame) {
-        let horizontal = Layout::horizontal([Constraint::Length(29), Constraint::Fill(1)]);
-        let [settings, charts] = frame.area().layout(&horizontal);
-        let vertical = Layout::vertical([Constraint::Fill(3), Constraint::Fill(2)]);
-        let [top, bottom] = charts.layout(&vertical);
+        let horizontal = Layout::horizontal([
+            Constraint::Length(29),
+            Constraint::Fill(1),
+            Constraint::Length(29),
+        ]);
+        let [settings, charts, blocks] = frame.area().layout(&horizontal);
+        let vertical = Layout::vertical([
+            Constraint::Fill(3),
+            Constraint::Fill(2),
+            Constraint::Length(6),
+        ]);
+        let [top, bottom, disturbances] = charts.layout(&vertical);
 
         self.render_input_output_charts(frame, top);
         self.render_settings(frame, settings);
+        self.render_blocks(frame, blocks);
         self.render_controller_chart(frame, bottom);
+        self.render_disturbances(frame, disturbances);
         self.render_edit_popup(frame);
+        match self.editing {
+            Editing::PlantPopup => self.plant.render_popup(frame),
+            Editing::InnerPlantPopup => self.inner_plant.render_popup(frame),
+            Editing::ControllerPopup => self.controller.render_popup(frame),
+            Editing::InnerControllerPopup => self.inner_controller.render_popup(frame),
+            Editing::Schedule => self
+                .schedule
+                .render_popup(frame, &self.plant.parameters()),
+            _ => (),
+        }
     }
 
     fn render_input_output_charts(&self, frame: &mut Frame, area: Rect) {
@@ -394,7 +907,7 @@ impl App {
                 Style::default().add_modifier(Modifier::BOLD),
             ),
         ];
-        let datasets = vec![
+        let mut datasets = vec![
             Dataset::default()
                 .name("reference")
                 .marker(symbols::Marker::Braille)
@@ -406,19 +919,72 @@ impl App {
                 .style(Style::default().fg(Color::Yellow))
                 .data(&self.plant_data),
         ];
+        if self.setpoint.is_enabled() {
+            datasets.push(
+                Dataset::default()
+                    .name("effective setpoint")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(Color::LightCyan))
+                    .data(&self.setpoint_data),
+            );
+        }
+        if let Some((name, _)) = self.plant.plotted_state() {
+            datasets.push(
+                Dataset::default()
+                    .name(name)
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(Color::LightRed))
+                    .data(&self.plant_state_data),
+            );
+        }
+        if self.output_disturbance.is_active() {
+            datasets.push(
+                Dataset::default()
+                    .name("output disturbance")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(Color::Blue))
+                    .data(&self.output_disturbance_data),
+            );
+        }
+        if self.sensor.is_enabled() {
+            datasets.push(
+                Dataset::default()
+                    .name("measured output")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(Color::LightMagenta))
+                    .data(&self.measured_data),
+            );
+        }
+        if self.topology == Topology::Cascade {
+            datasets.push(
+                Dataset::default()
+                    .name("inner plant output")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(Color::Green))
+                    .data(&self.inner_plant_data),
+            );
+        }
 
+        let mut title = vec![
+            " Start/stop the simulation ".into(),
+            "<s>".blue().bold(),
+            " Cascade ".into(),
+            // a model based controller cannot be the outer one of a cascade
+            if self.topology == Topology::Single && self.controller.uses_plant_model() {
+                "<t>".dark_gray()
+            } else {
+                "<t>".blue().bold()
+            },
+            " Schedule ".into(),
+            "<e>".blue().bold(),
+            " Quit ".into(),
+            "<q> ".blue().bold(),
+        ];
+        if self.guard.tripped().is_some() {
+            title.push(" DIVERGED ".white().on_red().bold());
+        }
         let chart = Chart::new(datasets)
-            .block(
-                Block::bordered().title_top(
-                    Line::from(vec![
-                        " Start/stop the simulation ".into(),
-                        "<s>".blue().bold(),
-                        " Quit ".into(),
-                        "<q> ".blue().bold(),
-                    ])
-                    .centered(),
-                ),
-            )
+            .block(Block::bordered().title_top(Line::from(title).centered()))
             .x_axis(
                 Axis::default()
                     .title("X Axis")
@@ -438,56 +1004,188 @@ impl App {
     }
 
     fn render_settings(&mut self, frame: &mut Frame, settings: Rect) {
-        let vertical = Layout::vertical([Constraint::Fill(1); 3]);
-        let [reference, plant, controller] = settings.layout(&vertical);
+        let cascade = self.topology == Topology::Cascade;
+        let blocks = if cascade { 5 } else { 3 };
+        let areas = Layout::vertical(vec![Constraint::Fill(1); blocks]).split(settings);
+        let (reference, plant, controller) = (areas[0], areas[1], areas[2]);
 
-        let outer_ref_block = if let Editing::Reference = self.editing {
-            Block::bordered()
-                .title_top(Line::from(vec![" Reference ".into(), "<ESC> ".blue().bold()]))
-                .cyan()
-        } else {
-            Block::bordered().title_top(Line::from(vec![" Reference ".into(), "<i/I> ".blue().bold()]))
-        };
+        let outer_ref_block = settings_block(
+            " Reference ",
+            "<i/I> ",
+            matches!(self.editing, Editing::Reference),
+        );
         let inner_ref_area = outer_ref_block.inner(reference);
         frame.render_widget(outer_ref_block, reference);
         self.reference
             .render(frame, inner_ref_area, &mut self.editing);
 
-        let outer_plant_block = if let Editing::Plant = self.editing {
-            Block::bordered()
-                .title_top(Line::from(vec![" Plant ".into(), "<ESC> ".blue().bold()]))
-                .cyan()
-        } else {
-            Block::bordered().title_top(Line::from(vec![" Plant ".into(), "<p/P> ".blue().bold()]))
-        };
+        let outer_plant_block = settings_block(
+            if cascade { " Outer plant " } else { " Plant " },
+            "<p/P> ",
+            matches!(self.editing, Editing::Plant | Editing::PlantPopup),
+        );
         let inner_plant_area = outer_plant_block.inner(plant);
         frame.render_widget(outer_plant_block, plant);
         self.plant
             .render(frame, inner_plant_area, &mut self.editing);
 
         let controller_state = &mut (self.is_controler_active, self.editing.clone());
-        let outer_controller_block = if let Editing::Controller = self.editing {
-            Block::bordered()
-                .title_top(Line::from(vec![" Controller ".into(), "<ESC> ".blue().bold()]))
-                .cyan()
-        } else {
-            Block::bordered().title_top(Line::from(vec![" Controller ".into(), "<c/C> ".blue().bold()]))
-        };
+        let outer_controller_block = settings_block(
+            if cascade { " Outer controller " } else { " Controller " },
+            "<c/C> ",
+            matches!(self.editing, Editing::Controller | Editing::ControllerPopup),
+        );
         let inner_controller_area = outer_controller_block.inner(controller);
         frame.render_widget(outer_controller_block, controller);
         self.controller
             .render(frame, inner_controller_area, controller_state);
 
-        self.render_settings_cursor(frame, reference, plant, controller);
+        if cascade {
+            let (cascade_plant, cascade_controller) = (areas[3], areas[4]);
+            let mut editing = self.editing.to_single_loop();
+
+            let block = settings_block(
+                " Inner plant ",
+                "<j/J> ",
+                matches!(self.editing, Editing::InnerPlant | Editing::InnerPlantPopup),
+            );
+            let area = block.inner(cascade_plant);
+            frame.render_widget(block, cascade_plant);
+            self.inner_plant.render(frame, area, &mut editing);
+
+            let block = settings_block(
+                " Inner controller ",
+                "<k/K> ",
+                matches!(
+                    self.editing,
+                    Editing::InnerController | Editing::InnerControllerPopup
+                ),
+            );
+            let area = block.inner(cascade_controller);
+            frame.render_widget(block, cascade_controller);
+            self.inner_controller
+                .render(frame, area, &mut (self.is_controler_active, editing));
+        }
+
+        self.render_settings_cursor(frame, &areas);
+    }
+
+    /// Renders the settings of the loop blocks that do not depend on the selected components.
+    fn render_blocks(&mut self, frame: &mut Frame, area: Rect) {
+        // shared in proportion to the number of settings of each block
+        let vertical = Layout::vertical([
+            Constraint::Fill(2),
+            Constraint::Fill(3),
+            Constraint::Fill(4),
+            Constraint::Fill(4),
+            Constraint::Length(4),
+        ]);
+        let [setpoint, feedforward, actuator, sensor, guard] = area.layout(&vertical);
+
+        let block = settings_block(
+            " Setpoint ",
+            "<r> ",
+            matches!(self.editing, Editing::Setpoint),
+        );
+        let inner = block.inner(setpoint);
+        frame.render_widget(block, setpoint);
+        self.setpoint.render(frame, inner, &mut self.editing);
+        if let Editing::Setpoint = self.editing {
+            let (x_offset, y_offset) = self.setpoint.get_cursor_offsets();
+            frame.set_cursor_position((setpoint.x + x_offset, setpoint.y + y_offset));
+        }
+
+        let block = settings_block(
+            " Feedforward ",
+            "<f> ",
+            matches!(self.editing, Editing::Feedforward),
+        );
+        let inner = block.inner(feedforward);
+        frame.render_widget(block, feedforward);
+        self.feedforward.render(frame, inner, &mut self.editing);
+        if let Editing::Feedforward = self.editing {
+            let (x_offset, y_offset) = self.feedforward.get_cursor_offsets();
+            frame.set_cursor_position((feedforward.x + x_offset, feedforward.y + y_offset));
+        }
+
+        let block = settings_block(
+            " Actuator ",
+            "<a> ",
+            matches!(self.editing, Editing::Actuator),
+        );
+        let inner = block.inner(actuator);
+        frame.render_widget(block, actuator);
+        self.actuator.render(frame, inner, &mut self.editing);
+        if let Editing::Actuator = self.editing {
+            let (x_offset, y_offset) = self.actuator.get_cursor_offsets();
+            frame.set_cursor_position((actuator.x + x_offset, actuator.y + y_offset));
+        }
+
+        let block = settings_block(" Sensor ", "<m> ", matches!(self.editing, Editing::Sensor));
+        let inner = block.inner(sensor);
+        frame.render_widget(block, sensor);
+        self.sensor.render(frame, inner, &mut self.editing);
+        if let Editing::Sensor = self.editing {
+            let (x_offset, y_offset) = self.sensor.get_cursor_offsets();
+            frame.set_cursor_position((sensor.x + x_offset, sensor.y + y_offset));
+        }
+
+        let block = settings_block(" Guard ", "<g> ", matches!(self.editing, Editing::Guard));
+        let inner = block.inner(guard);
+        frame.render_widget(block, guard);
+        self.guard.render(frame, inner, &mut self.editing);
+        if let Editing::Guard = self.editing {
+            let (x_offset, y_offset) = self.guard.get_cursor_offsets();
+            frame.set_cursor_position((guard.x + x_offset, guard.y + y_offset));
+        }
+    }
+
+    /// Renders the generators of the disturbances and the settings of the injected ones.
+    fn render_disturbances(&mut self, frame: &mut Frame, area: Rect) {
+        let horizontal = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Length(29),
+        ]);
+        let [load, output, injection] = area.layout(&horizontal);
+
+        for (channel, area) in [
+            (DisturbanceChannel::Load, load),
+            (DisturbanceChannel::Output, output),
+        ] {
+            let (disturbance, title, keys) = match channel {
+                DisturbanceChannel::Load => (&self.load, " Load disturbance ", "<l/L> "),
+                DisturbanceChannel::Output => {
+                    (&self.output_disturbance, " Output disturbance ", "<o/O> ")
+                }
+            };
+            let selected = matches!(self.editing, Editing::Disturbance(c) if c == channel);
+            let block = settings_block(title, keys, selected);
+            let inner = block.inner(area);
+            frame.render_widget(block, area);
+            disturbance.render(frame, inner, selected);
+            if selected {
+                let (x_offset, y_offset) = disturbance.get_cursor_offsets();
+                frame.set_cursor_position((area.x + x_offset, area.y + y_offset));
+            }
+        }
+
+        let block = settings_block(
+            " Inject ",
+            "<d> ",
+            matches!(self.editing, Editing::Injection),
+        );
+        let inner = block.inner(injection);
+        frame.render_widget(block, injection);
+        self.injection.render(frame, inner, &mut self.editing);
+        if let Editing::Injection = self.editing {
+            let (x_offset, y_offset) = self.injection.get_cursor_offsets();
+            frame.set_cursor_position((injection.x + x_offset, injection.y + y_offset));
+        }
     }
 
-    fn render_settings_cursor(
-        &self,
-        frame: &mut Frame,
-        reference: Rect,
-        plant: Rect,
-        controller: Rect,
-    ) {
+    fn render_settings_cursor(&self, frame: &mut Frame, areas: &[Rect]) {
+        let (reference, plant, controller) = (areas[0], areas[1], areas[2]);
         match self.editing {
             Editing::Reference => {
                 let (x_offset, y_offset) = self.reference.get_cursor_offsets();
@@ -501,6 +1199,14 @@ impl App {
                 let (x_offset, y_offset) = self.controller.get_cursor_offsets();
                 frame.set_cursor_position((controller.x + x_offset, controller.y + y_offset))
             }
+            Editing::InnerPlant => {
+                let (x_offset, y_offset) = self.inner_plant.get_cursor_offsets();
+                frame.set_cursor_position((areas[3].x + x_offset, areas[3].y + y_offset))
+            }
+            Editing::InnerController => {
+                let (x_offset, y_offset) = self.inner_controller.get_cursor_offsets();
+                frame.set_cursor_position((areas[4].x + x_offset, areas[4].y + y_offset))
+            }
             _ => (),
         }
     }
@@ -519,13 +1225,56 @@ impl App {
                 Style::default().add_modifier(Modifier::BOLD),
             ),
         ];
-        let datasets = vec![
+        let mut datasets = vec![
             Dataset::default()
                 .name("controller output")
                 .marker(symbols::Marker::Braille)
                 .style(Style::default().fg(Color::Yellow))
                 .data(&self.controller_data),
         ];
+        if self.topology == Topology::Cascade {
+            datasets.push(
+                Dataset::default()
+                    .name("inner controller output")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(Color::Magenta))
+                    .data(&self.inner_controller_data),
+            );
+        }
+        if self.feedforward.is_enabled() {
+            datasets.push(
+                Dataset::default()
+                    .name("feedforward")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(Color::LightBlue))
+                    .data(&self.feedforward_data),
+            );
+        }
+        if self.load.is_active() {
+            datasets.push(
+                Dataset::default()
+                    .name("load disturbance")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(Color::Red))
+                    .data(&self.load_data),
+            );
+        }
+        if self.actuator.is_enabled() {
+            datasets.push(
+                Dataset::default()
+                    .name("commanded input")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(Color::Gray))
+                    .data(&self.command_data),
+            );
+            datasets.push(
+                Dataset::default()
+                    .name("applied input")
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(Color::LightGreen))
+                    .data(&self.actuator_data),
+            );
+        }
 
         let chart = Chart::new(datasets)
             .block(
@@ -592,11 +1341,19 @@ impl App {
                 (selected_idx, "plant", items)
             }
             Editing::ControllerType(idx) => {
+                let cascade = self.topology == Topology::Cascade;
                 let items: Vec<ListItem> = CONTROLLER_REGISTRY
                     .lock()
                     .unwrap()
-                    .keys()
-                    .map(|name| ListItem::new(Span::raw(*name)))
+                    .iter()
+                    .map(|(name, new)| {
+                        // model based controllers cannot be chosen for the outer loop
+                        if cascade && new().uses_plant_model() {
+                            ListItem::new(Span::raw(*name).dark_gray())
+                        } else {
+                            ListItem::new(Span::raw(*name))
+                        }
+                    })
                     .collect();
 
                 let current = self.controller.name();
@@ -611,6 +1368,66 @@ impl App {
                 self.editing = Editing::ControllerType(selected_idx);
                 (selected_idx, "controller", items)
             }
+            Editing::InnerPlantType(idx) => {
+                let items: Vec<ListItem> = PLANT_REGISTRY
+                    .lock()
+                    .unwrap()
+                    .keys()
+                    .map(|name| ListItem::new(Span::raw(*name)))
+                    .collect();
+
+                let current = self.inner_plant.name();
+                let selected_idx = idx.or_else(|| {
+                    PLANT_REGISTRY
+                        .lock()
+                        .unwrap()
+                        .keys()
+                        .position(|n| *n == current)
+                });
+                self.editing = Editing::InnerPlantType(selected_idx);
+                (selected_idx, "inner plant", items)
+            }
+            Editing::InnerControllerType(idx) => {
+                let items: Vec<ListItem> = CONTROLLER_REGISTRY
+                    .lock()
+                    .unwrap()
+                    .keys()
+                    .map(|name| ListItem::new(Span::raw(*name)))
+                    .collect();
+
+                let current = self.inner_controller.name();
+                let selected_idx = idx.or_else(|| {
+                    CONTROLLER_REGISTRY
+                        .lock()
+                        .unwrap()
+                        .keys()
+                        .position(|n| *n == current)
+                });
+                self.editing = Editing::InnerControllerType(selected_idx);
+                (selected_idx, "inner controller", items)
+            }
+            Editing::DisturbanceType(channel, idx) => {
+                let registry = REFERENCE_REGISTRY.lock().unwrap();
+                let items: Vec<ListItem> = std::iter::once("Off")
+                    .chain(registry.keys().copied())
+                    .map(|name| ListItem::new(Span::raw(name)))
+                    .collect();
+
+                let (current, r#type) = match channel {
+                    DisturbanceChannel::Load => (self.load.generator_name(), "load disturbance"),
+                    DisturbanceChannel::Output => (
+                        self.output_disturbance.generator_name(),
+                        "plant output disturbance",
+                    ),
+                };
+                // the entries of the registry follow the "Off" entry
+                let selected_idx = idx.or_else(|| match current {
+                    Some(current) => registry.keys().position(|n| *n == current).map(|i| i + 1),
+                    None => Some(0),
+                });
+                self.editing = Editing::DisturbanceType(channel, selected_idx);
+                (selected_idx, r#type, items)
+            }
             _ => return,
         };
         let title = format!("Choose a {} type (ESC to close)", r#type);
@@ -641,6 +1458,40 @@ impl App {
         frame.render_stateful_widget(list, area, &mut state);
     }
 }
+/// Bordered block of a settings panel, highlighted while the panel is edited.
+fn settings_block<'a>(title: &'a str, keys: &'a str, selected: bool) -> Block<'a> {
+    if selected {
+        Block::bordered()
+            .title_top(Line::from(vec![title.into(), "<ESC> ".blue().bold()]))
+            .cyan()
+    } else {
+        Block::bordered().title_top(Line::from(vec![title.into(), keys.blue().bold()]))
+    }
+}
+
+/// Index highlighted after moving the selection of a type popup with `count` entries.
+fn step_selection(idx: Option<usize>, count: usize, forward: bool) -> usize {
+    match idx {
+        Some(idx) => cycle_index(idx, count, forward),
+        None => 0,
+    }
+}
+
+/// Appends a sample to a chart series, dropping the oldest one once the window is full.
+fn push_sample(data: &mut Vec<(f64, f64)>, point: Option<(f64, f64)>, capacity: usize) {
+    if data.len() >= capacity {
+        data.drain(0..1);
+    }
+    data.extend(point);
+}
+
+/// Advances the time of a disabled controller and returns the sample repeating its last output.
+fn hold_output(controller: &mut dyn Controller, data: &[(f64, f64)]) -> (f64, f64) {
+    let last_output = data.last().map_or(0.0, |(_, y)| *y);
+    let x = controller.next().unwrap_or((0.0, 0.0)).0;
+    (x, last_output)
+}
+
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     // Cut the given rectangle into three vertical pieces
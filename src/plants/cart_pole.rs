@@ -0,0 +1,326 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::Modifier;
+use ratatui::style::{Color, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::canvas::{Canvas, Line as CanvasLine, Rectangle};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::plants::Plant;
+use crate::utils::{Field, FieldKind, FieldList, NumericInput};
+use crate::{Editing, register_plant};
+
+const PLANT_NAME: &str = "CartPole";
+
+/// Gravitational acceleration in m/s^2.
+const G: f64 = 9.81;
+/// Runge-Kutta steps per sampling period.
+const SUB_STEPS: usize = 10;
+/// Size of the cart in the animation, in m.
+const CART_WIDTH: f64 = 0.5;
+const CART_HEIGHT: f64 = 0.2;
+
+/// Inverted pendulum hinged on a cart pushed by the force F along a horizontal track:
+///
+///   theta'' = (g sin(theta) - cos(theta) f) / (l (4/3 - m cos^2(theta) / (M + m)))
+///   x''     = f - m l theta'' cos(theta) / (M + m)
+///
+/// with f = (F + m l theta'^2 sin(theta) - b x') / (M + m), the pole angle theta measured from
+/// upright and positive to the right, l the distance to the center of mass of the uniform pole
+/// and b the friction of the cart. The full nonlinear equations are integrated with RK4
+/// sub-steps. The output is the angle in degrees or the cart position in m; the pole starts
+/// tilted by `theta0` so that it falls unless the controller catches it.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct CartPoleSystem {
+    x: f64,
+    Ts: f64,
+    output: CartPoleOutput,
+    M: f64,          // cart mass
+    m: f64,          // pole mass
+    l: f64,          // half length of the pole
+    b: f64,          // cart friction
+    theta0: f64,     // initial angle in degrees
+    state: [f64; 4], // position, speed, angle, angular speed
+    F: f64,
+    edit: Option<(CartPoleField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CartPoleOutput {
+    Angle,
+    Position,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CartPoleField {
+    Output,
+    CartMass,
+    PoleMass,
+    Length,
+    Friction,
+    InitialAngle,
+}
+
+const FIELDS: [CartPoleField; 6] = [
+    CartPoleField::Output,
+    CartPoleField::CartMass,
+    CartPoleField::PoleMass,
+    CartPoleField::Length,
+    CartPoleField::Friction,
+    CartPoleField::InitialAngle,
+];
+
+impl Field for CartPoleField {
+    fn label(self) -> &'static str {
+        match self {
+            CartPoleField::Output => "output",
+            CartPoleField::CartMass => "M",
+            CartPoleField::PoleMass => "m",
+            CartPoleField::Length => "l",
+            CartPoleField::Friction => "b",
+            CartPoleField::InitialAngle => "theta0",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            CartPoleField::Output => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+impl CartPoleSystem {
+    #[allow(non_snake_case)]
+    pub fn new(M: f64, m: f64, l: f64, b: f64, theta0: f64, Ts: f64) -> Self {
+        Self {
+            x: 0.0,
+            Ts,
+            output: CartPoleOutput::Angle,
+            M,
+            m,
+            l,
+            b,
+            theta0,
+            state: [0.0, 0.0, theta0.to_radians(), 0.0],
+            F: 0.0,
+            edit: None,
+        }
+    }
+
+    fn derivatives(&self, s: [f64; 4]) -> [f64; 4] {
+        let [_, dx, theta, dtheta] = s;
+        let (sin, cos) = theta.sin_cos();
+        let total = self.M + self.m;
+        let f = (self.F + self.m * self.l * dtheta * dtheta * sin - self.b * dx) / total;
+        let ddtheta = (G * sin - cos * f) / (self.l * (4.0 / 3.0 - self.m * cos * cos / total));
+        let ddx = f - self.m * self.l * ddtheta * cos / total;
+        [dx, ddx, dtheta, ddtheta]
+    }
+
+    fn value(&self, field: CartPoleField) -> f64 {
+        match field {
+            CartPoleField::Output => 0.0,
+            CartPoleField::CartMass => self.M,
+            CartPoleField::PoleMass => self.m,
+            CartPoleField::Length => self.l,
+            CartPoleField::Friction => self.b,
+            CartPoleField::InitialAngle => self.theta0,
+        }
+    }
+
+    /// Draws the cart and the pole, the view following the cart with marks every meter.
+    fn render_animation(&self, area: Rect, buf: &mut Buffer) {
+        let [position, _, theta, _] = self.state;
+        let pole = 2.0 * self.l;
+        let (bottom, top) = (-0.15, CART_HEIGHT + pole + 0.2);
+        // braille dots are about square, keep the world proportions
+        let half_width = (top - bottom) * f64::from(area.width) / f64::from(area.height) / 4.0;
+        let (left, right) = (position - half_width, position + half_width);
+        Canvas::default()
+            .marker(Marker::Braille)
+            .x_bounds([left, right])
+            .y_bounds([bottom, top])
+            .paint(|ctx| {
+                ctx.draw(&CanvasLine::new(left, 0.0, right, 0.0, Color::Gray));
+                for mark in (left.ceil() as i64)..=(right.floor() as i64) {
+                    let mark = mark as f64;
+                    ctx.draw(&CanvasLine::new(mark, 0.0, mark, -0.1, Color::Gray));
+                }
+                ctx.draw(&Rectangle::new(
+                    position - CART_WIDTH / 2.0,
+                    0.0,
+                    CART_WIDTH,
+                    CART_HEIGHT,
+                    Color::Yellow,
+                ));
+                ctx.draw(&CanvasLine::new(
+                    position,
+                    CART_HEIGHT,
+                    position + pole * theta.sin(),
+                    CART_HEIGHT + pole * theta.cos(),
+                    Color::Cyan,
+                ));
+            })
+            .render(area, buf);
+    }
+}
+
+impl Default for CartPoleSystem {
+    fn default() -> Self {
+        Self::new(1.0, 0.1, 0.5, 0.1, 5.0, 0.1)
+    }
+}
+
+impl FieldList for CartPoleSystem {
+    type Field = CartPoleField;
+
+    fn fields(&self) -> Vec<CartPoleField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: CartPoleField) -> String {
+        match field {
+            CartPoleField::Output => match self.output {
+                CartPoleOutput::Angle => "angle",
+                CartPoleOutput::Position => "position",
+            }
+            .to_string(),
+            _ => self.value(field).to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: CartPoleField, value: f64) {
+        match field {
+            CartPoleField::Output => {}
+            // the masses and the length divide the accelerations
+            CartPoleField::CartMass => {
+                if value > 0.0 {
+                    self.M = value;
+                }
+            }
+            CartPoleField::PoleMass => self.m = value.max(0.0),
+            CartPoleField::Length => {
+                if value > 0.0 {
+                    self.l = value;
+                }
+            }
+            CartPoleField::Friction => self.b = value.max(0.0),
+            CartPoleField::InitialAngle => self.theta0 = value,
+        }
+    }
+
+    fn cycle(&mut self, _field: CartPoleField, _forward: bool) {
+        self.output = match self.output {
+            CartPoleOutput::Angle => CartPoleOutput::Position,
+            CartPoleOutput::Position => CartPoleOutput::Angle,
+        };
+    }
+
+    fn field_edit(&self) -> Option<&(CartPoleField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(CartPoleField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for CartPoleSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_input(&mut self, u: f64) {
+        self.F = u;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.state = [0.0, 0.0, self.theta0.to_radians(), 0.0];
+        self.F = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        FIELDS
+            .iter()
+            .filter(|f| **f != CartPoleField::Output)
+            .map(|f| (f.label(), self.value(*f)))
+            .collect()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if let Some(field) = FIELDS.iter().find(|f| f.label() == name) {
+            self.set_value(*field, value);
+        }
+    }
+}
+
+impl Iterator for CartPoleSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let h = self.Ts / SUB_STEPS as f64;
+        let shift = |s: [f64; 4], d: [f64; 4], k: f64| std::array::from_fn(|i| s[i] + k * d[i]);
+        for _ in 0..SUB_STEPS {
+            let k1 = self.derivatives(self.state);
+            let k2 = self.derivatives(shift(self.state, k1, h / 2.0));
+            let k3 = self.derivatives(shift(self.state, k2, h / 2.0));
+            let k4 = self.derivatives(shift(self.state, k3, h));
+            self.state = std::array::from_fn(|i| {
+                self.state[i] + h / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i])
+            });
+        }
+        let y = match self.output {
+            CartPoleOutput::Angle => self.state[2].to_degrees(),
+            CartPoleOutput::Position => self.state[0],
+        };
+        let point = (self.x, y);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for CartPoleSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "Cart Pole",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        lines.push(Line::from(Span::styled(
+            format!(
+                "x = {:.2} theta = {:.1}",
+                self.state[0],
+                self.state[2].to_degrees()
+            ),
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        let [text, animation] =
+            Layout::vertical([Constraint::Length(lines.len() as u16), Constraint::Fill(1)])
+                .areas(area);
+        self.panel(lines).render(text, buf);
+        if animation.height >= 3 {
+            self.render_animation(animation, buf);
+        }
+    }
+}
+
+register_plant!(CartPoleSystem, PLANT_NAME);
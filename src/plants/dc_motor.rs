@@ -0,0 +1,292 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::plants::Plant;
+use crate::utils::{Field, FieldKind, FieldList, NumericInput};
+use crate::{Editing, register_plant};
+
+const PLANT_NAME: &str = "DCMotor";
+
+/// Upper bound on the Runge-Kutta steps per sampling period.
+const MAX_SUB_STEPS: f64 = 10_000.0;
+
+/// Armature controlled DC motor driven by the voltage v:
+///
+///   L di/dt = v - R i - K w
+///   J dw/dt = K i - b w - T_load
+///   dtheta/dt = w
+///
+/// with the same constant K for the back-EMF and the torque. The output is the speed w in rad/s
+/// or the shaft angle theta in rad. The electrical time constant L / R is usually far below the
+/// sampling time, so every period is integrated with as many RK4 steps as needed to keep the
+/// step under half of it.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct DCMotorSystem {
+    x: f64,
+    Ts: f64,
+    output: MotorOutput,
+    R: f64,      // armature resistance
+    L: f64,      // armature inductance
+    K: f64,      // back-EMF and torque constant
+    J: f64,      // rotor inertia
+    b: f64,      // viscous friction
+    T_load: f64, // load torque
+    i: f64,      // armature current
+    w: f64,      // speed
+    theta: f64,  // position
+    v: f64,
+    edit: Option<(MotorField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MotorOutput {
+    Speed,
+    Position,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MotorField {
+    Output,
+    R,
+    L,
+    K,
+    J,
+    B,
+    Load,
+}
+
+const FIELDS: [MotorField; 7] = [
+    MotorField::Output,
+    MotorField::R,
+    MotorField::L,
+    MotorField::K,
+    MotorField::J,
+    MotorField::B,
+    MotorField::Load,
+];
+
+impl Field for MotorField {
+    fn label(self) -> &'static str {
+        match self {
+            MotorField::Output => "output",
+            MotorField::R => "R",
+            MotorField::L => "L",
+            MotorField::K => "K",
+            MotorField::J => "J",
+            MotorField::B => "b",
+            MotorField::Load => "T_load",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            MotorField::Output => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+impl DCMotorSystem {
+    #[allow(non_snake_case)]
+    pub fn new(output: MotorOutput, R: f64, L: f64, K: f64, J: f64, b: f64, Ts: f64) -> Self {
+        Self {
+            x: 0.0,
+            Ts,
+            output,
+            R,
+            L,
+            K,
+            J,
+            b,
+            T_load: 0.0,
+            i: 0.0,
+            w: 0.0,
+            theta: 0.0,
+            v: 0.0,
+            edit: None,
+        }
+    }
+
+    /// Derivatives of the current and the speed.
+    fn derivatives(&self, i: f64, w: f64) -> (f64, f64) {
+        (
+            (self.v - self.R * i - self.K * w) / self.L,
+            (self.K * i - self.b * w - self.T_load) / self.J,
+        )
+    }
+
+    fn sub_steps(&self) -> usize {
+        let tau_e = self.L / self.R.max(f64::EPSILON);
+        (2.0 * self.Ts / tau_e).ceil().clamp(1.0, MAX_SUB_STEPS) as usize
+    }
+
+    fn value(&self, field: MotorField) -> f64 {
+        match field {
+            MotorField::Output => 0.0,
+            MotorField::R => self.R,
+            MotorField::L => self.L,
+            MotorField::K => self.K,
+            MotorField::J => self.J,
+            MotorField::B => self.b,
+            MotorField::Load => self.T_load,
+        }
+    }
+}
+
+impl Default for DCMotorSystem {
+    fn default() -> Self {
+        Self::new(MotorOutput::Speed, 2.0, 0.005, 0.5, 0.2, 0.01, 0.1)
+    }
+}
+
+impl FieldList for DCMotorSystem {
+    type Field = MotorField;
+
+    fn fields(&self) -> Vec<MotorField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: MotorField) -> String {
+        match field {
+            MotorField::Output => match self.output {
+                MotorOutput::Speed => "speed",
+                MotorOutput::Position => "position",
+            }
+            .to_string(),
+            _ => self.value(field).to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: MotorField, value: f64) {
+        match field {
+            MotorField::Output => {}
+            MotorField::R => self.R = value.max(0.0),
+            // the inductance and the inertia divide the derivatives
+            MotorField::L => {
+                if value > 0.0 {
+                    self.L = value;
+                }
+            }
+            MotorField::K => self.K = value,
+            MotorField::J => {
+                if value > 0.0 {
+                    self.J = value;
+                }
+            }
+            MotorField::B => self.b = value.max(0.0),
+            MotorField::Load => self.T_load = value,
+        }
+    }
+
+    fn cycle(&mut self, _field: MotorField, _forward: bool) {
+        self.output = match self.output {
+            MotorOutput::Speed => MotorOutput::Position,
+            MotorOutput::Position => MotorOutput::Speed,
+        };
+    }
+
+    fn field_edit(&self) -> Option<&(MotorField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(MotorField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for DCMotorSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_input(&mut self, u: f64) {
+        self.v = u;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.i = 0.0;
+        self.w = 0.0;
+        self.theta = 0.0;
+        self.v = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        FIELDS
+            .iter()
+            .filter(|f| **f != MotorField::Output)
+            .map(|f| (f.label(), self.value(*f)))
+            .collect()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if let Some(field) = FIELDS.iter().find(|f| f.label() == name) {
+            self.set_value(*field, value);
+        }
+    }
+}
+
+impl Iterator for DCMotorSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let steps = self.sub_steps();
+        let h = self.Ts / steps as f64;
+        for _ in 0..steps {
+            let (i, w) = (self.i, self.w);
+            let k1 = self.derivatives(i, w);
+            let k2 = self.derivatives(i + h / 2.0 * k1.0, w + h / 2.0 * k1.1);
+            let k3 = self.derivatives(i + h / 2.0 * k2.0, w + h / 2.0 * k2.1);
+            let k4 = self.derivatives(i + h * k3.0, w + h * k3.1);
+            self.i += h / 6.0 * (k1.0 + 2.0 * k2.0 + 2.0 * k3.0 + k4.0);
+            self.w += h / 6.0 * (k1.1 + 2.0 * k2.1 + 2.0 * k3.1 + k4.1);
+            // the angle integrates the speed with the same weights
+            self.theta += h / 6.0
+                * (w + 2.0 * (w + h / 2.0 * k1.1) + 2.0 * (w + h / 2.0 * k2.1) + w + h * k3.1);
+        }
+        let y = match self.output {
+            MotorOutput::Speed => self.w,
+            MotorOutput::Position => self.theta,
+        };
+        let point = (self.x, y);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for DCMotorSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "DC Motor",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        lines.push(Line::from(Span::styled(
+            format!("i = {:.2} w = {:.2}", self.i, self.w),
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_plant!(DCMotorSystem, PLANT_NAME);
@@ -6,6 +6,7 @@ use ratatui::style::{Style, Stylize};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{FrameExt, Paragraph, StatefulWidgetRef, Widget};
 
+use crate::linalg::{Matrix, StateSpace};
 use crate::plants::Plant;
 use crate::utils::NumericInput;
 use crate::{Editing, register_plant};
@@ -148,6 +149,33 @@ impl Plant for FirstOrderSystem {
     fn name(&self) -> &'static str {
         PLANT_NAME
     }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("a", self.a), ("b", self.b)]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        match name {
+            "a" => self.a = value,
+            "b" => self.b = value,
+            _ => {}
+        }
+    }
+
+    /// The state is the last output, x[k] = y_k.
+    fn state_space(&self) -> Option<StateSpace> {
+        Some(StateSpace {
+            a: Matrix::from_rows(&[&[self.a]]),
+            b: Matrix::column(&[self.b]),
+            c: Matrix::from_rows(&[&[1.0]]),
+            d: Matrix::zeros(1, 1),
+            ts: self.Ts,
+        })
+    }
+
+    fn state(&self) -> Option<Vec<f64>> {
+        Some(vec![self.y_k])
+    }
 }
 
 impl Iterator for FirstOrderSystem {
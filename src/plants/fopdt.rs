@@ -0,0 +1,235 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::linalg::{Matrix, StateSpace};
+use crate::plants::Plant;
+use crate::utils::{DelayLine, Field, FieldList, NumericInput};
+use crate::{Editing, register_plant};
+
+const PLANT_NAME: &str = "FOPDTSystem";
+
+/// First-order plus dead time system:
+///
+///   G(s) = K e^(-theta s) / (tau s + 1)
+///
+/// discretized with a zero order hold, y[k+1] = a y[k] + b u[k-d] with a = e^(-Ts/tau),
+/// b = K (1 - a) and the dead time rounded to d = theta / Ts samples of a delay buffer.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct FOPDTSystem {
+    x: f64,
+    Ts: f64,
+    K: f64,     // static gain
+    tau: f64,   // time constant
+    theta: f64, // dead time
+    a: f64,
+    b: f64,
+    u: f64,
+    y_k: f64,
+    delay: DelayLine,
+    edit: Option<(FOPDTField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FOPDTField {
+    Gain,
+    Tau,
+    Theta,
+}
+
+const FIELDS: [FOPDTField; 3] = [FOPDTField::Gain, FOPDTField::Tau, FOPDTField::Theta];
+
+impl Field for FOPDTField {
+    fn label(self) -> &'static str {
+        match self {
+            FOPDTField::Gain => "K",
+            FOPDTField::Tau => "tau",
+            FOPDTField::Theta => "theta",
+        }
+    }
+}
+
+impl FOPDTSystem {
+    #[allow(non_snake_case)]
+    pub fn new(K: f64, tau: f64, theta: f64, Ts: f64) -> Self {
+        let mut plant = Self {
+            x: 0.0,
+            Ts,
+            K,
+            tau,
+            theta,
+            a: 0.0,
+            b: 0.0,
+            u: 0.0,
+            y_k: 0.0,
+            delay: DelayLine::new(DelayLine::samples_for(theta, Ts)),
+            edit: None,
+        };
+        plant.update_coefficients();
+        plant
+    }
+
+    fn update_coefficients(&mut self) {
+        self.a = if self.tau > 0.0 {
+            (-self.Ts / self.tau).exp()
+        } else {
+            0.0
+        };
+        self.b = self.K * (1.0 - self.a);
+        self.delay
+            .resize(DelayLine::samples_for(self.theta, self.Ts));
+    }
+
+    fn value(&self, field: FOPDTField) -> f64 {
+        match field {
+            FOPDTField::Gain => self.K,
+            FOPDTField::Tau => self.tau,
+            FOPDTField::Theta => self.theta,
+        }
+    }
+}
+
+impl Default for FOPDTSystem {
+    fn default() -> Self {
+        Self::new(1.0, 2.0, 1.0, 0.1)
+    }
+}
+
+impl FieldList for FOPDTSystem {
+    type Field = FOPDTField;
+
+    fn fields(&self) -> Vec<FOPDTField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: FOPDTField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: FOPDTField, value: f64) {
+        match field {
+            FOPDTField::Gain => self.K = value,
+            FOPDTField::Tau => self.tau = value.max(0.0),
+            FOPDTField::Theta => self.theta = value.max(0.0),
+        }
+        self.update_coefficients();
+    }
+
+    fn field_edit(&self) -> Option<&(FOPDTField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(FOPDTField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for FOPDTSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_input(&mut self, u: f64) {
+        self.u = u;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.y_k = 0.0;
+        self.u = 0.0;
+        self.delay.reset();
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        FIELDS.iter().map(|f| (f.label(), self.value(*f))).collect()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if let Some(field) = FIELDS.iter().find(|f| f.label() == name) {
+            self.set_value(*field, value);
+        }
+    }
+
+    /// The state is the last output followed by the inputs waiting in the delay buffer,
+    /// x[k] = [y[k], u[k-1], ..., u[k-d]].
+    fn state_space(&self) -> Option<StateSpace> {
+        let d = DelayLine::samples_for(self.theta, self.Ts);
+        let mut a = Matrix::zeros(d + 1, d + 1);
+        let mut b = Matrix::zeros(d + 1, 1);
+        let mut c = Matrix::zeros(1, d + 1);
+        a[(0, 0)] = self.a;
+        c[(0, 0)] = 1.0;
+        if d == 0 {
+            b[(0, 0)] = self.b;
+        } else {
+            a[(0, d)] = self.b;
+            b[(1, 0)] = 1.0;
+            for i in 2..=d {
+                a[(i, i - 1)] = 1.0;
+            }
+        }
+        Some(StateSpace {
+            a,
+            b,
+            c,
+            d: Matrix::zeros(1, 1),
+            ts: self.Ts,
+        })
+    }
+
+    fn state(&self) -> Option<Vec<f64>> {
+        Some(
+            std::iter::once(self.y_k)
+                .chain(self.delay.newest_first())
+                .collect(),
+        )
+    }
+}
+
+impl Iterator for FOPDTSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let u_delayed = self.delay.push(self.u);
+        self.y_k = self.a * self.y_k + self.b * u_delayed;
+        let point = (self.x, self.y_k);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for FOPDTSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "First Order + Dead Time",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        lines.push(Line::from(Span::styled(
+            format!("y_k = {:.2}", self.y_k),
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_plant!(FOPDTSystem, PLANT_NAME);
@@ -0,0 +1,261 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::linalg::{Matrix, StateSpace};
+use crate::plants::Plant;
+use crate::utils::{DelayLine, Field, FieldList, NumericInput};
+use crate::{Editing, register_plant};
+
+const PLANT_NAME: &str = "IntegratorSystem";
+
+/// Integrating process with an optional lag and dead time:
+///
+///   G(s) = K e^(-theta s) / (s (tau s + 1))
+///
+/// The output keeps ramping as long as the delayed input is not zero, so the plant has no
+/// steady state in open loop. Discretized exactly with a zero order hold: the lag state
+/// z[k+1] = a z[k] + (1 - a) u and y[k+1] = y[k] + K (Ts u + tau (1 - a) (z[k] - u)) with
+/// a = e^(-Ts/tau), where u is the input delayed by d = theta / Ts samples.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct IntegratorSystem {
+    x: f64,
+    Ts: f64,
+    K: f64,     // integration gain
+    tau: f64,   // lag time constant
+    theta: f64, // dead time
+    a: f64,
+    z: f64, // lag output
+    u: f64,
+    y_k: f64,
+    delay: DelayLine,
+    edit: Option<(IntegratorField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum IntegratorField {
+    Gain,
+    Tau,
+    Theta,
+}
+
+const FIELDS: [IntegratorField; 3] = [
+    IntegratorField::Gain,
+    IntegratorField::Tau,
+    IntegratorField::Theta,
+];
+
+impl Field for IntegratorField {
+    fn label(self) -> &'static str {
+        match self {
+            IntegratorField::Gain => "K",
+            IntegratorField::Tau => "tau",
+            IntegratorField::Theta => "theta",
+        }
+    }
+}
+
+impl IntegratorSystem {
+    #[allow(non_snake_case)]
+    pub fn new(K: f64, tau: f64, theta: f64, Ts: f64) -> Self {
+        let mut plant = Self {
+            x: 0.0,
+            Ts,
+            K,
+            tau,
+            theta,
+            a: 0.0,
+            z: 0.0,
+            u: 0.0,
+            y_k: 0.0,
+            delay: DelayLine::new(DelayLine::samples_for(theta, Ts)),
+            edit: None,
+        };
+        plant.update_coefficients();
+        plant
+    }
+
+    fn update_coefficients(&mut self) {
+        self.a = if self.tau > 0.0 {
+            (-self.Ts / self.tau).exp()
+        } else {
+            0.0
+        };
+        self.delay
+            .resize(DelayLine::samples_for(self.theta, self.Ts));
+    }
+
+    fn value(&self, field: IntegratorField) -> f64 {
+        match field {
+            IntegratorField::Gain => self.K,
+            IntegratorField::Tau => self.tau,
+            IntegratorField::Theta => self.theta,
+        }
+    }
+}
+
+impl Default for IntegratorSystem {
+    fn default() -> Self {
+        Self::new(1.0, 0.5, 0.0, 0.1)
+    }
+}
+
+impl FieldList for IntegratorSystem {
+    type Field = IntegratorField;
+
+    fn fields(&self) -> Vec<IntegratorField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: IntegratorField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: IntegratorField, value: f64) {
+        match field {
+            IntegratorField::Gain => self.K = value,
+            IntegratorField::Tau => self.tau = value.max(0.0),
+            IntegratorField::Theta => self.theta = value.max(0.0),
+        }
+        self.update_coefficients();
+    }
+
+    fn field_edit(&self) -> Option<&(IntegratorField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(IntegratorField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for IntegratorSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_input(&mut self, u: f64) {
+        self.u = u;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.y_k = 0.0;
+        self.z = 0.0;
+        self.u = 0.0;
+        self.delay.reset();
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        FIELDS.iter().map(|f| (f.label(), self.value(*f))).collect()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if let Some(field) = FIELDS.iter().find(|f| f.label() == name) {
+            self.set_value(*field, value);
+        }
+    }
+
+    /// The state is the last output, the lag output when there is a lag, and the inputs waiting
+    /// in the delay buffer, x[k] = [y[k], z[k], u[k-1], ..., u[k-d]].
+    fn state_space(&self) -> Option<StateSpace> {
+        let lag = usize::from(self.tau > 0.0);
+        let d = DelayLine::samples_for(self.theta, self.Ts);
+        let n = 1 + lag + d;
+        let mut a = Matrix::zeros(n, n);
+        let mut b = Matrix::zeros(n, 1);
+        let mut c = Matrix::zeros(1, n);
+        let b_u = self.K * (self.Ts - self.tau * (1.0 - self.a));
+        let b_z = self.K * self.tau * (1.0 - self.a);
+        a[(0, 0)] = 1.0;
+        c[(0, 0)] = 1.0;
+        if lag == 1 {
+            a[(0, 1)] = b_z;
+            a[(1, 1)] = self.a;
+        }
+        // the input reaching the integrator, either u[k] or the oldest delayed sample
+        let mut drive = |row: usize, gain: f64| {
+            if d == 0 {
+                b[(row, 0)] = gain;
+            } else {
+                a[(row, n - 1)] = gain;
+            }
+        };
+        drive(0, b_u);
+        if lag == 1 {
+            drive(1, 1.0 - self.a);
+        }
+        if d > 0 {
+            b[(1 + lag, 0)] = 1.0;
+            for i in 2 + lag..n {
+                a[(i, i - 1)] = 1.0;
+            }
+        }
+        Some(StateSpace {
+            a,
+            b,
+            c,
+            d: Matrix::zeros(1, 1),
+            ts: self.Ts,
+        })
+    }
+
+    fn state(&self) -> Option<Vec<f64>> {
+        let lag = (self.tau > 0.0).then_some(self.z);
+        Some(
+            std::iter::once(self.y_k)
+                .chain(lag)
+                .chain(self.delay.newest_first())
+                .collect(),
+        )
+    }
+}
+
+impl Iterator for IntegratorSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let u = self.delay.push(self.u);
+        self.y_k += self.K * (self.Ts * u + self.tau * (1.0 - self.a) * (self.z - u));
+        self.z = self.a * self.z + (1.0 - self.a) * u;
+        let point = (self.x, self.y_k);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for IntegratorSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "Integrator",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        lines.push(Line::from(Span::styled(
+            format!("y_k = {:.2}", self.y_k),
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_plant!(IntegratorSystem, PLANT_NAME);
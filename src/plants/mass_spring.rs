@@ -0,0 +1,236 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::plants::Plant;
+use crate::utils::{Field, FieldList, NumericInput};
+use crate::{Editing, register_plant};
+
+const PLANT_NAME: &str = "MassSpringFriction";
+
+/// Integration steps per sampling period.
+const SUB_STEPS: usize = 100;
+
+/// Mass on a spring and a damper, pushed by the force F against dry friction:
+///
+///   m x'' = F - k x - c x' - F_f
+///
+/// While the mass is at rest it sticks as long as the applied force |F - k x| stays below the
+/// static friction F_s. Once it slips, the Coulomb friction F_f = F_c sign(x') opposes the
+/// motion until the speed crosses zero again. With integral action this produces the hunting
+/// limit cycles typical of positioning systems. Integrated with semi-implicit Euler sub-steps.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct MassSpringSystem {
+    x: f64,
+    Ts: f64,
+    m: f64,   // mass
+    k: f64,   // spring stiffness
+    c: f64,   // viscous damping
+    F_c: f64, // Coulomb friction
+    F_s: f64, // static friction
+    position: f64,
+    speed: f64,
+    F: f64,
+    edit: Option<(MassSpringField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MassSpringField {
+    Mass,
+    Stiffness,
+    Damping,
+    Coulomb,
+    Static,
+}
+
+const FIELDS: [MassSpringField; 5] = [
+    MassSpringField::Mass,
+    MassSpringField::Stiffness,
+    MassSpringField::Damping,
+    MassSpringField::Coulomb,
+    MassSpringField::Static,
+];
+
+impl Field for MassSpringField {
+    fn label(self) -> &'static str {
+        match self {
+            MassSpringField::Mass => "m",
+            MassSpringField::Stiffness => "k",
+            MassSpringField::Damping => "c",
+            MassSpringField::Coulomb => "F_c",
+            MassSpringField::Static => "F_s",
+        }
+    }
+}
+
+impl MassSpringSystem {
+    #[allow(non_snake_case)]
+    pub fn new(m: f64, k: f64, c: f64, F_c: f64, F_s: f64, Ts: f64) -> Self {
+        Self {
+            x: 0.0,
+            Ts,
+            m,
+            k,
+            c,
+            F_c,
+            F_s,
+            position: 0.0,
+            speed: 0.0,
+            F: 0.0,
+            edit: None,
+        }
+    }
+
+    fn step(&mut self, h: f64) {
+        let force = self.F - self.k * self.position - self.c * self.speed;
+        let friction = if self.speed != 0.0 {
+            self.F_c * self.speed.signum()
+        } else if force.abs() <= self.F_s {
+            // stuck
+            return;
+        } else {
+            self.F_c * force.signum()
+        };
+        let speed = self.speed + h * (force - friction) / self.m;
+        // a reversal goes through rest, where the mass may stick
+        self.speed = if self.speed != 0.0 && speed.signum() != self.speed.signum() {
+            0.0
+        } else {
+            speed
+        };
+        self.position += h * self.speed;
+    }
+
+    fn value(&self, field: MassSpringField) -> f64 {
+        match field {
+            MassSpringField::Mass => self.m,
+            MassSpringField::Stiffness => self.k,
+            MassSpringField::Damping => self.c,
+            MassSpringField::Coulomb => self.F_c,
+            MassSpringField::Static => self.F_s,
+        }
+    }
+}
+
+impl Default for MassSpringSystem {
+    fn default() -> Self {
+        Self::new(1.0, 1.0, 0.5, 1.0, 1.5, 0.1)
+    }
+}
+
+impl FieldList for MassSpringSystem {
+    type Field = MassSpringField;
+
+    fn fields(&self) -> Vec<MassSpringField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: MassSpringField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: MassSpringField, value: f64) {
+        match field {
+            // the mass divides the acceleration
+            MassSpringField::Mass => {
+                if value > 0.0 {
+                    self.m = value;
+                }
+            }
+            MassSpringField::Stiffness => self.k = value.max(0.0),
+            MassSpringField::Damping => self.c = value.max(0.0),
+            MassSpringField::Coulomb => self.F_c = value.max(0.0),
+            MassSpringField::Static => self.F_s = value.max(0.0),
+        }
+    }
+
+    fn field_edit(&self) -> Option<&(MassSpringField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(MassSpringField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for MassSpringSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_input(&mut self, u: f64) {
+        self.F = u;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.position = 0.0;
+        self.speed = 0.0;
+        self.F = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        FIELDS.iter().map(|f| (f.label(), self.value(*f))).collect()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if let Some(field) = FIELDS.iter().find(|f| f.label() == name) {
+            self.set_value(*field, value);
+        }
+    }
+}
+
+impl Iterator for MassSpringSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let h = self.Ts / SUB_STEPS as f64;
+        for _ in 0..SUB_STEPS {
+            self.step(h);
+        }
+        let point = (self.x, self.position);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for MassSpringSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "Mass Spring Friction",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        let motion = if self.speed == 0.0 {
+            "stuck"
+        } else {
+            "slipping"
+        };
+        lines.push(Line::from(Span::styled(
+            format!("v = {:.2} {}", self.speed, motion),
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_plant!(MassSpringSystem, PLANT_NAME);
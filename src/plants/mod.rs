@@ -4,9 +4,21 @@ use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::Mutex;
 use crate::Editing;
+use crate::linalg::StateSpace;
 
+pub mod cart_pole;
+pub mod dc_motor;
 pub mod first_order;
+pub mod fopdt;
+pub mod integrator;
+pub mod mass_spring;
 pub mod second_order;
+pub mod state_space;
+pub mod tank;
+pub mod thermal;
+pub mod unstable;
+pub mod vehicle;
+pub mod transfer_function;
 
 #[macro_export]
 macro_rules! register_plant {
@@ -28,7 +40,31 @@ pub trait Plant: StatefulWidgetRef<State = Editing> + Iterator<Item = (f64, f64)
     fn reset(&mut self);
 
     fn render(&self, frame: &mut Frame, area: Rect, state: &mut Editing);
+    /// Renders an additional editor on top of the whole frame while `Editing::PlantPopup` is
+    /// active. The popup is responsible for placing the cursor.
+    fn render_popup(&self, _frame: &mut Frame) {}
     fn name(&self) -> &'static str;
+
+    /// Discrete state-space model of the plant at its current parameters, `None` if the plant
+    /// is not linear.
+    fn state_space(&self) -> Option<StateSpace> {
+        None
+    }
+    /// Current state vector in the coordinates of `state_space`.
+    fn state(&self) -> Option<Vec<f64>> {
+        None
+    }
+    /// Name and value of an internal signal the user chose to plot alongside the output.
+    fn plotted_state(&self) -> Option<(String, f64)> {
+        None
+    }
+    /// Names and current values of the scalar parameters, as labelled in the settings panel.
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+    /// Changes a parameter returned by `parameters` as if it was edited in the settings panel,
+    /// ignoring unknown names.
+    fn set_parameter(&mut self, _name: &str, _value: f64) {}
 }
 
 
@@ -6,6 +6,7 @@ use ratatui::style::{Style, Stylize};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{FrameExt, Paragraph, StatefulWidgetRef, Widget};
 
+use crate::linalg::{Matrix, StateSpace};
 use crate::plants::Plant;
 use crate::utils::NumericInput;
 use crate::{Editing, register_plant};
@@ -206,6 +207,45 @@ impl Plant for SecondOrderSystem {
     fn name(&self) -> &'static str {
         PLANT_NAME
     }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("zeta", self.zeta), ("wn", self.wn)]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        match name {
+            "zeta" => self.set_zeta(value),
+            "wn" => self.set_wn(value),
+            _ => {}
+        }
+    }
+
+    /// Transposed direct form realization of the difference equation, extended with the last
+    /// output as the measured state:
+    ///
+    /// s1[k] = −a1y[k−1]−a2y[k−2]+b1u[k−1]+b2u[k−2], s2[k] = −a2y[k−1]+b2u[k−1], x3[k] = y[k−1]
+    fn state_space(&self) -> Option<StateSpace> {
+        let (a1, a2) = self.a;
+        let (b0, b1, b2) = self.b;
+        Some(StateSpace {
+            a: Matrix::from_rows(&[&[-a1, 1.0, 0.0], &[-a2, 0.0, 0.0], &[1.0, 0.0, 0.0]]),
+            b: Matrix::column(&[b1 - a1 * b0, b2 - a2 * b0, b0]),
+            c: Matrix::from_rows(&[&[0.0, 0.0, 1.0]]),
+            d: Matrix::zeros(1, 1),
+            ts: self.Ts,
+        })
+    }
+
+    fn state(&self) -> Option<Vec<f64>> {
+        let (a1, a2) = self.a;
+        let (_, b1, b2) = self.b;
+        // u.0 holds the input of the previous step until the next one is set
+        Some(vec![
+            -a1 * self.y_k.0 - a2 * self.y_k.1 + b1 * self.u.0 + b2 * self.u.1,
+            -a2 * self.y_k.0 + b2 * self.u.0,
+            self.y_k.0,
+        ])
+    }
 }
 
 impl Iterator for SecondOrderSystem {
@@ -0,0 +1,453 @@
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, FrameExt, Paragraph, StatefulWidgetRef, Widget};
+
+use crate::linalg::{Discretization, Matrix, StateSpace, discretize};
+use crate::plants::Plant;
+use crate::utils::{Field, FieldKind, FieldList, NumericInput, cycle_index};
+use crate::{Editing, centered_rect, register_plant};
+
+const PLANT_NAME: &str = "StateSpace";
+
+/// Largest order accepted by the matrix editor.
+const MAX_ORDER: usize = 8;
+/// Width of the row labels and of a column in the matrix popup.
+const LABEL_WIDTH: usize = 5;
+const CELL_WIDTH: usize = 8;
+
+/// Plant given by a continuous-time state-space model
+///
+///   dx/dt = A x + B u
+///   y     = C x + D u
+///
+/// of any order, sampled with a zero order hold through the matrix exponential. The matrices are
+/// edited in a popup laid out as [A B; C D], and one state can be plotted next to the output. The
+/// input goes through a scalar gain, which scales B and D without editing the matrices.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct StateSpaceSystem {
+    x: f64,
+    Ts: f64,
+    a: Matrix, // continuous state matrix
+    b: Matrix,
+    c: Matrix,
+    d: Matrix,
+    gain: f64,              // input gain
+    model: StateSpace,      // zero order hold discretization
+    state: Matrix,          // x[k]
+    plotted: Option<usize>, // state shown on the chart
+    u: f64,
+    y_k: f64,
+    edit: Option<(SSField, NumericInput)>,
+    table_edit: Option<(usize, usize, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SSField {
+    Order,
+    Gain,
+    Plot,
+}
+
+const FIELDS: [SSField; 3] = [SSField::Order, SSField::Gain, SSField::Plot];
+
+impl Field for SSField {
+    fn label(self) -> &'static str {
+        match self {
+            SSField::Order => "order",
+            SSField::Gain => "gain",
+            SSField::Plot => "plot",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            SSField::Plot => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+impl StateSpaceSystem {
+    #[allow(non_snake_case)]
+    pub fn new(a: Matrix, b: Matrix, c: Matrix, d: Matrix, Ts: f64) -> Self {
+        let n = a.rows();
+        let model = discretize(&a, &b, &c, &d, Ts, Discretization::ZeroOrderHold)
+            .expect("the zero order hold always exists");
+        Self {
+            x: 0.0,
+            Ts,
+            a,
+            b,
+            c,
+            d,
+            gain: 1.0,
+            model,
+            state: Matrix::zeros(n, 1),
+            plotted: None,
+            u: 0.0,
+            y_k: 0.0,
+            edit: None,
+            table_edit: None,
+        }
+    }
+
+    fn order(&self) -> usize {
+        self.a.rows()
+    }
+
+    fn update_model(&mut self) {
+        self.model = discretize(
+            &self.a,
+            &self.b.scale(self.gain),
+            &self.c,
+            &self.d.scale(self.gain),
+            self.Ts,
+            Discretization::ZeroOrderHold,
+        )
+        .expect("the zero order hold always exists");
+    }
+
+    /// Changes the order keeping the entries of the common states. The added states are stable
+    /// first order lags decoupled from the others.
+    fn set_order(&mut self, n: usize) {
+        let n = n.clamp(1, MAX_ORDER);
+        let old = self.order();
+        if n == old {
+            return;
+        }
+        let keep = n.min(old);
+        let resize = |m: &Matrix, rows: usize, cols: usize| {
+            let mut resized = Matrix::zeros(rows, cols);
+            for i in 0..rows.min(m.rows()) {
+                for j in 0..cols.min(m.cols()) {
+                    resized[(i, j)] = m[(i, j)];
+                }
+            }
+            resized
+        };
+        self.a = resize(&self.a, n, n);
+        for i in keep..n {
+            self.a[(i, i)] = -1.0;
+        }
+        self.b = resize(&self.b, n, 1);
+        self.c = resize(&self.c, 1, n);
+        self.state = resize(&self.state, n, 1);
+        if self.plotted.is_some_and(|i| i >= n) {
+            self.plotted = None;
+        }
+        self.update_model();
+    }
+
+    /// Whether the input reaches the output directly through D.
+    fn has_feedthrough(&self) -> bool {
+        self.model.d[(0, 0)] != 0.0
+    }
+
+    fn set_gain(&mut self, gain: f64) {
+        self.gain = gain;
+        self.update_model();
+    }
+
+    /// Entry of the [A B; C D] table.
+    fn cell(&self, row: usize, column: usize) -> f64 {
+        let n = self.order();
+        match (row < n, column < n) {
+            (true, true) => self.a[(row, column)],
+            (true, false) => self.b[(row, 0)],
+            (false, true) => self.c[(0, column)],
+            (false, false) => self.d[(0, 0)],
+        }
+    }
+
+    fn cell_input(&self, row: usize, column: usize) -> NumericInput {
+        NumericInput::from(self.cell(row, column).to_string())
+    }
+
+    fn commit_cell(&mut self) {
+        let Some((row, column, input)) = self.table_edit.as_ref() else {
+            return;
+        };
+        let (row, column) = (*row, *column);
+        let Some(num) = input.as_f64() else {
+            return;
+        };
+        let n = self.order();
+        match (row < n, column < n) {
+            (true, true) => self.a[(row, column)] = num,
+            (true, false) => self.b[(row, 0)] = num,
+            (false, true) => self.c[(0, column)] = num,
+            (false, false) => self.d[(0, 0)] = num,
+        }
+        self.update_model();
+    }
+
+    fn edit_table(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        let size = self.order() + 1;
+        let Some((row, column, input)) = self.table_edit.as_mut() else {
+            return;
+        };
+        let (row, column) = (*row, *column);
+
+        match k.code {
+            KeyCode::Esc => {
+                self.table_edit = None;
+                *editing = Editing::Plant;
+            }
+            KeyCode::Enter => {
+                self.commit_cell();
+                self.table_edit = None;
+                *editing = Editing::Plant;
+            }
+            KeyCode::Down | KeyCode::Up => {
+                self.commit_cell();
+                let row = cycle_index(row, size, k.code == KeyCode::Down);
+                self.table_edit = Some((row, column, self.cell_input(row, column)));
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.commit_cell();
+                let column = cycle_index(column, size, k.code == KeyCode::Tab);
+                self.table_edit = Some((row, column, self.cell_input(row, column)));
+            }
+            code => input.handle_key(code),
+        }
+    }
+}
+
+impl Default for StateSpaceSystem {
+    fn default() -> Self {
+        Self::new(
+            Matrix::from_rows(&[&[0.0, 1.0], &[-1.0, -1.0]]),
+            Matrix::column(&[0.0, 1.0]),
+            Matrix::from_rows(&[&[1.0, 0.0]]),
+            Matrix::zeros(1, 1),
+            0.1,
+        )
+    }
+}
+
+impl FieldList for StateSpaceSystem {
+    type Field = SSField;
+
+    fn fields(&self) -> Vec<SSField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: SSField) -> String {
+        match field {
+            SSField::Order => self.order().to_string(),
+            SSField::Gain => self.gain.to_string(),
+            SSField::Plot => match self.plotted {
+                Some(i) => format!("x{}", i + 1),
+                None => "off".to_string(),
+            },
+        }
+    }
+
+    fn set_value(&mut self, field: SSField, value: f64) {
+        match field {
+            SSField::Order => self.set_order(value.max(0.0) as usize),
+            SSField::Gain => self.set_gain(value),
+            SSField::Plot => {}
+        }
+    }
+
+    /// Cycles the plotted state through off, x1, ..., xn.
+    fn cycle(&mut self, _field: SSField, forward: bool) {
+        let idx = self.plotted.map_or(0, |i| i + 1);
+        let idx = cycle_index(idx, self.order() + 1, forward);
+        self.plotted = idx.checked_sub(1);
+    }
+
+    fn field_edit(&self) -> Option<&(SSField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(SSField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for StateSpaceSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        if let Editing::PlantPopup = editing {
+            self.edit_table(editing, k);
+            return;
+        }
+        match k.code {
+            KeyCode::Tab => {
+                self.table_edit = Some((0, 0, self.cell_input(0, 0)));
+                *editing = Editing::PlantPopup;
+            }
+            _ => self.edit_fields(editing, k),
+        }
+    }
+
+    fn set_input(&mut self, u: f64) {
+        self.u = u;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        let n = self.order();
+        self.x = 0.0;
+        self.y_k = 0.0;
+        self.u = 0.0;
+        self.state = Matrix::zeros(n, 1);
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+
+    fn render_popup(&self, frame: &mut Frame) {
+        let n = self.order();
+        let area = centered_rect(60, 50, frame.area());
+        let block = Block::default()
+            .title(" Matrices [A B; C D] (Tab/Up/Down move, Enter/ESC close) ")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let label_style = Style::default().gray().add_modifier(Modifier::BOLD);
+        let header = std::iter::once(format!("{:<width$}", "", width = LABEL_WIDTH))
+            .chain(
+                (0..n).map(|j| format!("{:<width$} ", format!("x{}", j + 1), width = CELL_WIDTH)),
+            )
+            .chain(std::iter::once("u".to_string()))
+            .collect::<String>();
+        let mut lines = vec![Line::from(Span::styled(header, label_style))];
+        for row in 0..=n {
+            let label = if row < n {
+                format!("x{}'", row + 1)
+            } else {
+                "y".to_string()
+            };
+            let mut spans = vec![Span::styled(
+                format!("{:<width$}", label, width = LABEL_WIDTH),
+                label_style,
+            )];
+            spans.extend((0..=n).map(|column| match self.table_edit.as_ref() {
+                Some((r, c, input)) if *r == row && *c == column => Span::styled(
+                    format!("{:<width$} ", input.value, width = CELL_WIDTH),
+                    Style::default().cyan(),
+                ),
+                _ => Span::raw(format!(
+                    "{:<width$} ",
+                    self.cell(row, column),
+                    width = CELL_WIDTH
+                )),
+            }));
+            lines.push(Line::from(spans).add_modifier(Modifier::BOLD));
+        }
+        frame.render_widget(Paragraph::new(lines), inner);
+
+        if let Some((row, column, input)) = self.table_edit.as_ref() {
+            let x = inner.x + (LABEL_WIDTH + column * (CELL_WIDTH + 1) + input.cursor) as u16;
+            let y = inner.y + 1 + *row as u16;
+            frame.set_cursor_position((x, y));
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    /// The matrices are not scalar parameters, only the input gain can be changed.
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![(SSField::Gain.label(), self.gain)]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if name == SSField::Gain.label() {
+            self.set_gain(value);
+        }
+    }
+
+    /// The state is the sampled model state. A non-zero D makes the output depend on the input
+    /// held over the last sample, which is then appended, x[k] = [x_1, ..., x_n, u[k-1]].
+    fn state_space(&self) -> Option<StateSpace> {
+        if !self.has_feedthrough() {
+            return Some(self.model.clone());
+        }
+        let n = self.order();
+        let mut a = Matrix::zeros(n + 1, n + 1);
+        let mut b = Matrix::zeros(n + 1, 1);
+        let mut c = Matrix::zeros(1, n + 1);
+        a.set_block(0, 0, &self.model.a);
+        b.set_block(0, 0, &self.model.b);
+        b[(n, 0)] = 1.0;
+        c.set_block(0, 0, &self.model.c);
+        c.set_block(0, n, &self.model.d);
+        Some(StateSpace {
+            a,
+            b,
+            c,
+            d: Matrix::zeros(1, 1),
+            ts: self.Ts,
+        })
+    }
+
+    fn state(&self) -> Option<Vec<f64>> {
+        let held = self.has_feedthrough().then_some(self.u);
+        Some(
+            (0..self.order())
+                .map(|i| self.state[(i, 0)])
+                .chain(held)
+                .collect(),
+        )
+    }
+
+    fn plotted_state(&self) -> Option<(String, f64)> {
+        self.plotted
+            .map(|i| (format!("state x{}", i + 1), self.state[(i, 0)]))
+    }
+}
+
+impl Iterator for StateSpaceSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let u = Matrix::column(&[self.u]);
+        self.state = &(&self.model.a * &self.state) + &(&self.model.b * &u);
+        self.y_k = (&(&self.model.c * &self.state) + &(&self.model.d * &u))[(0, 0)];
+        let point = (self.x, self.y_k);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for StateSpaceSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "State Space",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        lines.push(Line::from(vec![
+            Span::styled("matrices ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw("<Tab>").blue().bold(),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!("y_k = {:.2}", self.y_k),
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_plant!(StateSpaceSystem, PLANT_NAME);
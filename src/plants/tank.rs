@@ -0,0 +1,291 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::plants::Plant;
+use crate::utils::{Field, FieldKind, FieldList, NumericInput};
+use crate::{Editing, register_plant};
+
+const PLANT_NAME: &str = "TankLevel";
+
+/// Gravitational acceleration in m/s^2.
+const G: f64 = 9.81;
+/// Runge-Kutta steps per sampling period.
+const SUB_STEPS: usize = 10;
+
+/// Liquid level in open tanks fed by a pump, with Torricelli outflow through a bottom orifice:
+///
+///   area dh1/dt = q - a1 sqrt(2 g h1)
+///   area dh2/dt = a1 sqrt(2 g h1) - a2 sqrt(2 g h2)   (coupled tanks only)
+///
+/// The input is the pump flow q in m^3/s, which cannot be negative, and the output the level of
+/// the last tank in m. The levels saturate between empty and the overflow height `h_max`. Since
+/// the outflow grows with the square root of the level, the static gain 2 h / q and the time
+/// constant 2 area h / q both increase with the operating point.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct TankSystem {
+    x: f64,
+    Ts: f64,
+    tanks: Tanks,
+    area: f64,  // cross section of each tank
+    a1: f64,    // outlet area of the first tank
+    a2: f64,    // outlet area of the second tank
+    h_max: f64, // overflow level
+    h: (f64, f64),
+    q: f64,
+    edit: Option<(TankField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tanks {
+    Single,
+    Coupled,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TankField {
+    Tanks,
+    Area,
+    Outlet1,
+    Outlet2,
+    MaxLevel,
+}
+
+impl Field for TankField {
+    fn label(self) -> &'static str {
+        match self {
+            TankField::Tanks => "tanks",
+            TankField::Area => "area",
+            TankField::Outlet1 => "a1",
+            TankField::Outlet2 => "a2",
+            TankField::MaxLevel => "h_max",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            TankField::Tanks => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+/// Torricelli outflow through an orifice of area `a` under the level `h`.
+fn outflow(a: f64, h: f64) -> f64 {
+    a * (2.0 * G * h.max(0.0)).sqrt()
+}
+
+impl TankSystem {
+    #[allow(non_snake_case)]
+    pub fn new(tanks: Tanks, area: f64, a1: f64, a2: f64, h_max: f64, Ts: f64) -> Self {
+        Self {
+            x: 0.0,
+            Ts,
+            tanks,
+            area,
+            a1,
+            a2,
+            h_max,
+            h: (0.0, 0.0),
+            q: 0.0,
+            edit: None,
+        }
+    }
+
+    fn derivatives(&self, h: (f64, f64)) -> (f64, f64) {
+        let q1 = outflow(self.a1, h.0);
+        let dh1 = (self.q - q1) / self.area;
+        let dh2 = match self.tanks {
+            Tanks::Single => 0.0,
+            Tanks::Coupled => (q1 - outflow(self.a2, h.1)) / self.area,
+        };
+        (dh1, dh2)
+    }
+
+    fn saturate(&self, h: (f64, f64)) -> (f64, f64) {
+        (h.0.clamp(0.0, self.h_max), h.1.clamp(0.0, self.h_max))
+    }
+
+    fn output(&self) -> f64 {
+        match self.tanks {
+            Tanks::Single => self.h.0,
+            Tanks::Coupled => self.h.1,
+        }
+    }
+
+    fn value(&self, field: TankField) -> f64 {
+        match field {
+            TankField::Tanks => 0.0,
+            TankField::Area => self.area,
+            TankField::Outlet1 => self.a1,
+            TankField::Outlet2 => self.a2,
+            TankField::MaxLevel => self.h_max,
+        }
+    }
+}
+
+impl Default for TankSystem {
+    fn default() -> Self {
+        Self::new(Tanks::Single, 0.1, 0.02, 0.02, 20.0, 0.1)
+    }
+}
+
+impl FieldList for TankSystem {
+    type Field = TankField;
+
+    fn fields(&self) -> Vec<TankField> {
+        match self.tanks {
+            Tanks::Single => vec![
+                TankField::Tanks,
+                TankField::Area,
+                TankField::Outlet1,
+                TankField::MaxLevel,
+            ],
+            Tanks::Coupled => vec![
+                TankField::Tanks,
+                TankField::Area,
+                TankField::Outlet1,
+                TankField::Outlet2,
+                TankField::MaxLevel,
+            ],
+        }
+    }
+
+    fn text(&self, field: TankField) -> String {
+        match field {
+            TankField::Tanks => match self.tanks {
+                Tanks::Single => "single",
+                Tanks::Coupled => "coupled",
+            }
+            .to_string(),
+            _ => self.value(field).to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: TankField, value: f64) {
+        match field {
+            TankField::Tanks => {}
+            TankField::Area => {
+                if value > 0.0 {
+                    self.area = value;
+                }
+            }
+            TankField::Outlet1 => self.a1 = value.max(0.0),
+            TankField::Outlet2 => self.a2 = value.max(0.0),
+            TankField::MaxLevel => {
+                self.h_max = value.max(0.0);
+                self.h = self.saturate(self.h);
+            }
+        }
+    }
+
+    fn cycle(&mut self, _field: TankField, _forward: bool) {
+        self.tanks = match self.tanks {
+            Tanks::Single => Tanks::Coupled,
+            Tanks::Coupled => Tanks::Single,
+        };
+        self.h.1 = 0.0;
+    }
+
+    fn field_edit(&self) -> Option<&(TankField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(TankField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for TankSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_input(&mut self, u: f64) {
+        // a pump cannot draw liquid back
+        self.q = u.max(0.0);
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.h = (0.0, 0.0);
+        self.q = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        self.fields()
+            .iter()
+            .filter(|f| **f != TankField::Tanks)
+            .map(|f| (f.label(), self.value(*f)))
+            .collect()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if let Some(field) = self.fields().into_iter().find(|f| f.label() == name) {
+            self.set_value(field, value);
+        }
+    }
+}
+
+impl Iterator for TankSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let dt = self.Ts / SUB_STEPS as f64;
+        let shift = |h: (f64, f64), d: (f64, f64), k: f64| (h.0 + k * d.0, h.1 + k * d.1);
+        for _ in 0..SUB_STEPS {
+            let k1 = self.derivatives(self.h);
+            let k2 = self.derivatives(shift(self.h, k1, dt / 2.0));
+            let k3 = self.derivatives(shift(self.h, k2, dt / 2.0));
+            let k4 = self.derivatives(shift(self.h, k3, dt));
+            let d = (
+                (k1.0 + 2.0 * k2.0 + 2.0 * k3.0 + k4.0) / 6.0,
+                (k1.1 + 2.0 * k2.1 + 2.0 * k3.1 + k4.1) / 6.0,
+            );
+            self.h = self.saturate(shift(self.h, d, dt));
+        }
+        let point = (self.x, self.output());
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for TankSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "Tank Level",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        let levels = match self.tanks {
+            Tanks::Single => format!("h = {:.2}", self.h.0),
+            Tanks::Coupled => format!("h1 = {:.2} h2 = {:.2}", self.h.0, self.h.1),
+        };
+        lines.push(Line::from(Span::styled(
+            levels,
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_plant!(TankSystem, PLANT_NAME);
@@ -0,0 +1,232 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::plants::Plant;
+use crate::utils::{DelayLine, Field, FieldList, NumericInput};
+use crate::{Editing, register_plant};
+
+const PLANT_NAME: &str = "ThermalProcess";
+
+/// Heated body losing heat to its surroundings:
+///
+///   C dT/dt = P(t - theta) - h (T - T_amb)
+///
+/// The heater power P is limited to [0, P_max], so the body can only cool down passively through
+/// the loss coefficient h. The heat reaches the body after the transport delay theta, and the
+/// output is the temperature seen through a first order sensor of time constant tau_s. The body
+/// and the sensor are discretized with a zero order hold, the delay rounded to whole samples.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct ThermalSystem {
+    x: f64,
+    Ts: f64,
+    P_max: f64, // heater power limit
+    C: f64,     // thermal capacity
+    h: f64,     // loss coefficient to ambient
+    T_amb: f64, // ambient temperature
+    tau_s: f64, // sensor time constant
+    theta: f64, // transport delay
+    T: f64,     // body temperature
+    T_m: f64,   // measured temperature
+    P: f64,     // applied heater power
+    delay: DelayLine,
+    edit: Option<(ThermalField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ThermalField {
+    MaxPower,
+    Capacity,
+    Loss,
+    Ambient,
+    SensorLag,
+    Delay,
+}
+
+const FIELDS: [ThermalField; 6] = [
+    ThermalField::MaxPower,
+    ThermalField::Capacity,
+    ThermalField::Loss,
+    ThermalField::Ambient,
+    ThermalField::SensorLag,
+    ThermalField::Delay,
+];
+
+impl Field for ThermalField {
+    fn label(self) -> &'static str {
+        match self {
+            ThermalField::MaxPower => "P_max",
+            ThermalField::Capacity => "C",
+            ThermalField::Loss => "h",
+            ThermalField::Ambient => "T_amb",
+            ThermalField::SensorLag => "tau_s",
+            ThermalField::Delay => "theta",
+        }
+    }
+}
+
+impl ThermalSystem {
+    #[allow(non_snake_case)]
+    pub fn new(P_max: f64, C: f64, h: f64, T_amb: f64, tau_s: f64, theta: f64, Ts: f64) -> Self {
+        Self {
+            x: 0.0,
+            Ts,
+            P_max,
+            C,
+            h,
+            T_amb,
+            tau_s,
+            theta,
+            T: T_amb,
+            T_m: T_amb,
+            P: 0.0,
+            delay: DelayLine::new(DelayLine::samples_for(theta, Ts)),
+            edit: None,
+        }
+    }
+
+    fn value(&self, field: ThermalField) -> f64 {
+        match field {
+            ThermalField::MaxPower => self.P_max,
+            ThermalField::Capacity => self.C,
+            ThermalField::Loss => self.h,
+            ThermalField::Ambient => self.T_amb,
+            ThermalField::SensorLag => self.tau_s,
+            ThermalField::Delay => self.theta,
+        }
+    }
+}
+
+impl Default for ThermalSystem {
+    fn default() -> Self {
+        Self::new(100.0, 50.0, 5.0, 0.0, 1.0, 0.5, 0.1)
+    }
+}
+
+impl FieldList for ThermalSystem {
+    type Field = ThermalField;
+
+    fn fields(&self) -> Vec<ThermalField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: ThermalField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: ThermalField, value: f64) {
+        match field {
+            ThermalField::MaxPower => self.P_max = value.max(0.0),
+            // the capacity divides the heat balance
+            ThermalField::Capacity => {
+                if value > 0.0 {
+                    self.C = value;
+                }
+            }
+            ThermalField::Loss => self.h = value.max(0.0),
+            ThermalField::Ambient => self.T_amb = value,
+            ThermalField::SensorLag => self.tau_s = value.max(0.0),
+            ThermalField::Delay => {
+                self.theta = value.max(0.0);
+                self.delay
+                    .resize(DelayLine::samples_for(self.theta, self.Ts));
+            }
+        }
+    }
+
+    fn field_edit(&self) -> Option<&(ThermalField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(ThermalField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for ThermalSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_input(&mut self, u: f64) {
+        self.P = u.clamp(0.0, self.P_max);
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.T = self.T_amb;
+        self.T_m = self.T_amb;
+        self.P = 0.0;
+        self.delay.reset();
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        FIELDS.iter().map(|f| (f.label(), self.value(*f))).collect()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if let Some(field) = FIELDS.iter().find(|f| f.label() == name) {
+            self.set_value(*field, value);
+        }
+    }
+}
+
+impl Iterator for ThermalSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let power = self.delay.push(self.P);
+        let a = (-self.Ts * self.h / self.C).exp();
+        // without losses the body integrates the power
+        self.T = if self.h > 0.0 {
+            a * self.T + (1.0 - a) * (self.T_amb + power / self.h)
+        } else {
+            self.T + self.Ts * power / self.C
+        };
+        let b = if self.tau_s > 0.0 {
+            (-self.Ts / self.tau_s).exp()
+        } else {
+            0.0
+        };
+        self.T_m = b * self.T_m + (1.0 - b) * self.T;
+        let point = (self.x, self.T_m);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for ThermalSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "Thermal Process",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        lines.push(Line::from(Span::styled(
+            format!("P = {:.1} T = {:.2}", self.P, self.T),
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_plant!(ThermalSystem, PLANT_NAME);
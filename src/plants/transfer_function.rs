@@ -0,0 +1,426 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::linalg::{Discretization, Matrix, StateSpace, discretize};
+use crate::plants::Plant;
+use crate::utils::{DelayLine, Field, FieldKind, FieldList, NumericInput};
+use crate::{Editing, register_plant};
+
+const PLANT_NAME: &str = "TransferFunction";
+
+/// Plant given by a continuous transfer function with optional dead time
+///
+///   G(s) = K (b_0 s^m + ... + b_m) / (a_0 s^n + ... + a_n) e^(-theta s),  m <= n
+///
+/// entered as space separated coefficients in descending powers of s, the gain K scaling the
+/// numerator. The rational part is put in controllable canonical form and sampled with a zero
+/// order hold or the Tustin transform, giving the difference equation
+///
+///   y[k] = -a_1 y[k-1] - ... - a_n y[k-n] + b_0 u[k] + ... + b_n u[k-n]
+///
+/// which is run in transposed direct form II. The dead time is rounded to whole samples.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct TransferFunctionSystem {
+    x: f64,
+    Ts: f64,
+    num: Vec<f64>, // continuous numerator coefficients
+    den: Vec<f64>, // continuous denominator coefficients
+    gain: f64,     // gain K applied to the numerator
+    method: Discretization,
+    theta: f64,  // dead time
+    b: Vec<f64>, // discrete numerator, length n + 1
+    a: Vec<f64>, // discrete monic denominator, length n + 1
+    s: Vec<f64>, // direct form II states
+    u: f64,
+    y_k: f64,
+    delay: DelayLine,
+    edit: Option<(TFField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TFField {
+    Num,
+    Den,
+    Gain,
+    Method,
+    Theta,
+}
+
+const FIELDS: [TFField; 5] = [
+    TFField::Num,
+    TFField::Den,
+    TFField::Gain,
+    TFField::Method,
+    TFField::Theta,
+];
+
+impl Field for TFField {
+    fn label(self) -> &'static str {
+        match self {
+            TFField::Num => "num",
+            TFField::Den => "den",
+            TFField::Gain => "gain",
+            TFField::Method => "method",
+            TFField::Theta => "theta",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            TFField::Num | TFField::Den => FieldKind::List,
+            TFField::Method => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+/// Coefficients without leading zeros.
+fn strip_leading_zeros(p: &[f64]) -> Vec<f64> {
+    p.iter().copied().skip_while(|c| *c == 0.0).collect()
+}
+
+fn format_coefficients(p: &[f64]) -> String {
+    p.iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl TransferFunctionSystem {
+    /// Panics if the Tustin transform of the model does not exist for this sampling time.
+    #[allow(non_snake_case)]
+    pub fn new(num: Vec<f64>, den: Vec<f64>, method: Discretization, theta: f64, Ts: f64) -> Self {
+        let coefficients = Self::sample(&num, &den, 1.0, method, Ts)
+            .expect("the transfer function has a pole at s = 2 / Ts");
+        let mut plant = Self {
+            x: 0.0,
+            Ts,
+            num,
+            den,
+            gain: 1.0,
+            method,
+            theta,
+            b: Vec::new(),
+            a: Vec::new(),
+            s: Vec::new(),
+            u: 0.0,
+            y_k: 0.0,
+            delay: DelayLine::new(DelayLine::samples_for(theta, Ts)),
+            edit: None,
+        };
+        plant.set_coefficients(coefficients);
+        plant
+    }
+
+    /// Accepts the polynomials if the denominator is non-zero, the transfer function proper and
+    /// its discretization exists.
+    fn set_polynomials(&mut self, num: &[f64], den: &[f64]) {
+        let den = strip_leading_zeros(den);
+        let mut num = strip_leading_zeros(num);
+        if num.is_empty() {
+            num.push(0.0);
+        }
+        if den.is_empty() || num.len() > den.len() {
+            return;
+        }
+        let Some(coefficients) = Self::sample(&num, &den, self.gain, self.method, self.Ts) else {
+            return;
+        };
+        self.num = num;
+        self.den = den;
+        self.set_coefficients(coefficients);
+    }
+
+    /// Switches the discretization method unless the Tustin transform does not exist.
+    fn set_method(&mut self, method: Discretization) {
+        if let Some(coefficients) = Self::sample(&self.num, &self.den, self.gain, method, self.Ts) {
+            self.method = method;
+            self.set_coefficients(coefficients);
+        }
+    }
+
+    fn set_gain(&mut self, gain: f64) {
+        let sampled = Self::sample(&self.num, &self.den, gain, self.method, self.Ts);
+        if let Some(coefficients) = sampled {
+            self.gain = gain;
+            self.set_coefficients(coefficients);
+        }
+    }
+
+    /// Discrete numerator and denominator of gain num / den, none when the Tustin transform is
+    /// singular.
+    #[allow(non_snake_case)]
+    fn sample(
+        num: &[f64],
+        den: &[f64],
+        gain: f64,
+        method: Discretization,
+        Ts: f64,
+    ) -> Option<(Vec<f64>, Vec<f64>)> {
+        let n = den.len() - 1;
+        let a0 = den[0];
+        let den: Vec<f64> = den.iter().map(|c| c / a0).collect();
+        let mut padded = vec![0.0; n + 1 - num.len()];
+        padded.extend(num.iter().map(|c| gain * c / a0));
+        let num = padded;
+
+        // controllable canonical form, x = [z, z', ..., z^(n-1)] with den(s) z = u
+        let mut a = Matrix::zeros(n, n);
+        let mut b = Matrix::zeros(n, 1);
+        let mut c = Matrix::zeros(1, n);
+        for i in 0..n {
+            if i + 1 < n {
+                a[(i, i + 1)] = 1.0;
+            }
+            a[(n - 1, i)] = -den[n - i];
+            c[(0, i)] = num[n - i] - den[n - i] * num[0];
+        }
+        if n > 0 {
+            b[(n - 1, 0)] = 1.0;
+        }
+        let d = Matrix::from_rows(&[&[num[0]]]);
+        discretize(&a, &b, &c, &d, Ts, method).map(|model| model.transfer_function())
+    }
+
+    /// Takes the discrete coefficients, keeping the filter states when the order is unchanged.
+    fn set_coefficients(&mut self, (b, a): (Vec<f64>, Vec<f64>)) {
+        self.b = b;
+        self.a = a;
+        let n = self.a.len() - 1;
+        if self.s.len() != n {
+            self.s = vec![0.0; n];
+        }
+    }
+
+    fn set_theta(&mut self, theta: f64) {
+        self.theta = theta.max(0.0);
+        self.delay
+            .resize(DelayLine::samples_for(self.theta, self.Ts));
+    }
+}
+
+impl Default for TransferFunctionSystem {
+    fn default() -> Self {
+        Self::new(
+            vec![1.0],
+            vec![1.0, 3.0, 3.0, 1.0],
+            Discretization::ZeroOrderHold,
+            0.0,
+            0.1,
+        )
+    }
+}
+
+impl FieldList for TransferFunctionSystem {
+    type Field = TFField;
+
+    fn fields(&self) -> Vec<TFField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: TFField) -> String {
+        match field {
+            TFField::Num => format_coefficients(&self.num),
+            TFField::Den => format_coefficients(&self.den),
+            TFField::Gain => self.gain.to_string(),
+            TFField::Method => match self.method {
+                Discretization::ZeroOrderHold => "ZOH".to_string(),
+                Discretization::Tustin => "Tustin".to_string(),
+            },
+            TFField::Theta => self.theta.to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: TFField, value: f64) {
+        match field {
+            TFField::Gain => self.set_gain(value),
+            TFField::Theta => self.set_theta(value),
+            TFField::Num | TFField::Den | TFField::Method => {}
+        }
+    }
+
+    fn commit(&mut self, field: TFField, input: &NumericInput) {
+        match field {
+            TFField::Num => {
+                if let Some(num) = input.as_f64_list() {
+                    let den = self.den.clone();
+                    self.set_polynomials(&num, &den);
+                }
+            }
+            TFField::Den => {
+                if let Some(den) = input.as_f64_list() {
+                    let num = self.num.clone();
+                    self.set_polynomials(&num, &den);
+                }
+            }
+            _ => {
+                if let Some(value) = input.as_f64() {
+                    self.set_value(field, value);
+                }
+            }
+        }
+    }
+
+    fn cycle(&mut self, _field: TFField, _forward: bool) {
+        self.set_method(match self.method {
+            Discretization::ZeroOrderHold => Discretization::Tustin,
+            Discretization::Tustin => Discretization::ZeroOrderHold,
+        });
+    }
+
+    fn field_edit(&self) -> Option<&(TFField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(TFField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for TransferFunctionSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_input(&mut self, u: f64) {
+        self.u = u;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.y_k = 0.0;
+        self.u = 0.0;
+        self.s.iter_mut().for_each(|s| *s = 0.0);
+        self.delay.reset();
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    /// The polynomials are not scalar parameters, only the gain and the dead time can be changed.
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            (TFField::Gain.label(), self.gain),
+            (TFField::Theta.label(), self.theta),
+        ]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if name == TFField::Gain.label() {
+            self.set_gain(value);
+        } else if name == TFField::Theta.label() {
+            self.set_theta(value);
+        }
+    }
+
+    /// The state is the direct form II states, the last output and the inputs waiting in the
+    /// delay buffer, x[k] = [s_1, ..., s_n, y[k], u[k-1], ..., u[k-d]].
+    fn state_space(&self) -> Option<StateSpace> {
+        let n = self.s.len();
+        let d = DelayLine::samples_for(self.theta, self.Ts);
+        let order = n + 1 + d;
+        let mut a = Matrix::zeros(order, order);
+        let mut b = Matrix::zeros(order, 1);
+        let mut c = Matrix::zeros(1, order);
+        // the input reaching the rational part, either u[k] or the oldest delayed sample
+        let drive = |row: usize, gain: f64, a: &mut Matrix, b: &mut Matrix| {
+            if d == 0 {
+                b[(row, 0)] += gain;
+            } else {
+                a[(row, order - 1)] += gain;
+            }
+        };
+        for i in 0..n {
+            a[(i, 0)] = -self.a[i + 1];
+            if i + 1 < n {
+                a[(i, i + 1)] = 1.0;
+            }
+            drive(i, self.b[i + 1] - self.a[i + 1] * self.b[0], &mut a, &mut b);
+        }
+        if n > 0 {
+            a[(n, 0)] = 1.0;
+        }
+        drive(n, self.b[0], &mut a, &mut b);
+        c[(0, n)] = 1.0;
+        if d > 0 {
+            b[(n + 1, 0)] = 1.0;
+            for i in n + 2..order {
+                a[(i, i - 1)] = 1.0;
+            }
+        }
+        Some(StateSpace {
+            a,
+            b,
+            c,
+            d: Matrix::zeros(1, 1),
+            ts: self.Ts,
+        })
+    }
+
+    fn state(&self) -> Option<Vec<f64>> {
+        Some(
+            self.s
+                .iter()
+                .copied()
+                .chain(std::iter::once(self.y_k))
+                .chain(self.delay.newest_first())
+                .collect(),
+        )
+    }
+}
+
+impl Iterator for TransferFunctionSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        let u = self.delay.push(self.u);
+        let n = self.s.len();
+        self.y_k = self.b[0] * u + self.s.first().copied().unwrap_or(0.0);
+        for i in 0..n {
+            let next = self.s.get(i + 1).copied().unwrap_or(0.0);
+            self.s[i] = next + self.b[i + 1] * u - self.a[i + 1] * self.y_k;
+        }
+        let point = (self.x, self.y_k);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for TransferFunctionSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "Transfer Function",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        let info_style = Style::default().gray().add_modifier(Modifier::BOLD);
+        lines.push(Line::from(Span::styled(
+            format!("order {}", self.s.len()),
+            info_style,
+        )));
+        lines.push(Line::from(Span::styled(
+            format!("y_k = {:.2}", self.y_k),
+            info_style,
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_plant!(TransferFunctionSystem, PLANT_NAME);
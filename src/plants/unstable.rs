@@ -0,0 +1,204 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::linalg::{Matrix, StateSpace};
+use crate::plants::Plant;
+use crate::utils::{Field, FieldList, NumericInput};
+use crate::{Editing, register_plant};
+
+const PLANT_NAME: &str = "UnstableFirstOrder";
+
+/// First order system with a pole in the right half-plane:
+///
+///   G(s) = K / (tau s - 1)
+///
+/// discretized with a zero order hold, y[k+1] = a y[k] + b u[k] with a = e^(Ts/tau) > 1 and
+/// b = K (a - 1). Any deviation grows by e every tau seconds in open loop, so the controller
+/// needs a proportional gain above 1/K to stabilize it.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct UnstableSystem {
+    x: f64,
+    Ts: f64,
+    K: f64,   // gain
+    tau: f64, // time constant of the unstable pole
+    a: f64,
+    b: f64,
+    u: f64,
+    y_k: f64,
+    edit: Option<(UnstableField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum UnstableField {
+    Gain,
+    Tau,
+}
+
+const FIELDS: [UnstableField; 2] = [UnstableField::Gain, UnstableField::Tau];
+
+impl Field for UnstableField {
+    fn label(self) -> &'static str {
+        match self {
+            UnstableField::Gain => "K",
+            UnstableField::Tau => "tau",
+        }
+    }
+}
+
+impl UnstableSystem {
+    #[allow(non_snake_case)]
+    pub fn new(K: f64, tau: f64, Ts: f64) -> Self {
+        let mut plant = Self {
+            x: 0.0,
+            Ts,
+            K,
+            tau,
+            a: 0.0,
+            b: 0.0,
+            u: 0.0,
+            y_k: 0.0,
+            edit: None,
+        };
+        plant.update_coefficients();
+        plant
+    }
+
+    fn update_coefficients(&mut self) {
+        self.a = (self.Ts / self.tau).exp();
+        self.b = self.K * (self.a - 1.0);
+    }
+
+    fn value(&self, field: UnstableField) -> f64 {
+        match field {
+            UnstableField::Gain => self.K,
+            UnstableField::Tau => self.tau,
+        }
+    }
+}
+
+impl Default for UnstableSystem {
+    fn default() -> Self {
+        Self::new(1.0, 2.0, 0.1)
+    }
+}
+
+impl FieldList for UnstableSystem {
+    type Field = UnstableField;
+
+    fn fields(&self) -> Vec<UnstableField> {
+        FIELDS.to_vec()
+    }
+
+    fn text(&self, field: UnstableField) -> String {
+        self.value(field).to_string()
+    }
+
+    fn set_value(&mut self, field: UnstableField, value: f64) {
+        match field {
+            UnstableField::Gain => self.K = value,
+            // the time constant divides the growth rate
+            UnstableField::Tau => {
+                if value > 0.0 {
+                    self.tau = value;
+                }
+            }
+        }
+        self.update_coefficients();
+    }
+
+    fn field_edit(&self) -> Option<&(UnstableField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(UnstableField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for UnstableSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_input(&mut self, u: f64) {
+        self.u = u;
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.y_k = 0.0;
+        self.u = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        FIELDS.iter().map(|f| (f.label(), self.value(*f))).collect()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if let Some(field) = FIELDS.iter().find(|f| f.label() == name) {
+            self.set_value(*field, value);
+        }
+    }
+
+    fn state_space(&self) -> Option<StateSpace> {
+        Some(StateSpace {
+            a: Matrix::from_rows(&[&[self.a]]),
+            b: Matrix::from_rows(&[&[self.b]]),
+            c: Matrix::from_rows(&[&[1.0]]),
+            d: Matrix::zeros(1, 1),
+            ts: self.Ts,
+        })
+    }
+
+    fn state(&self) -> Option<Vec<f64>> {
+        Some(vec![self.y_k])
+    }
+}
+
+impl Iterator for UnstableSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.y_k = self.a * self.y_k + self.b * self.u;
+        let point = (self.x, self.y_k);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for UnstableSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "Unstable First Order",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        lines.push(Line::from(Span::styled(
+            format!("y_k = {:.2} pole = {:.3}", self.y_k, self.a),
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_plant!(UnstableSystem, PLANT_NAME);
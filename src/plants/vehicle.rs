@@ -0,0 +1,311 @@
+use std::f64::consts::PI;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{FrameExt, StatefulWidgetRef, Widget};
+
+use crate::plants::Plant;
+use crate::utils::{Field, FieldKind, FieldList, NumericInput, cycle_index};
+use crate::{Editing, register_plant};
+
+const PLANT_NAME: &str = "VehicleCruise";
+
+/// Gravitational acceleration in m/s^2.
+const G: f64 = 9.81;
+/// Air density in kg/m^3.
+const RHO: f64 = 1.2;
+/// Runge-Kutta steps per sampling period.
+const SUB_STEPS: usize = 10;
+
+/// Longitudinal dynamics of a car driving on a road of slope alpha:
+///
+///   m dv/dt = F - rho CdA v^2 / 2 - C_rr m g cos(alpha) - m g sin(alpha)
+///
+/// The input is the traction force F in kN, limited to [0, F_max] since the cruise control does
+/// not brake, and the output the speed v in m/s, which cannot become negative. The road grade
+/// tan(alpha) in percent follows a profile over the simulated time that acts as an unmeasured
+/// disturbance, and can be plotted next to the speed.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct VehicleSystem {
+    x: f64,
+    Ts: f64,
+    profile: GradeProfile,
+    grade: f64,  // largest road grade in percent
+    period: f64, // period of the grade profile
+    m: f64,      // vehicle mass
+    CdA: f64,    // drag coefficient times frontal area
+    C_rr: f64,   // rolling resistance coefficient
+    F_max: f64,  // traction force limit in kN
+    v: f64,
+    F: f64,
+    slope: f64, // grade of the last sampling period
+    edit: Option<(VehicleField, NumericInput)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GradeProfile {
+    Flat,
+    Hills,
+    Waves,
+}
+
+const PROFILES: [GradeProfile; 3] = [GradeProfile::Flat, GradeProfile::Hills, GradeProfile::Waves];
+
+impl GradeProfile {
+    fn label(self) -> &'static str {
+        match self {
+            GradeProfile::Flat => "flat",
+            GradeProfile::Hills => "hills",
+            GradeProfile::Waves => "waves",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum VehicleField {
+    Profile,
+    Grade,
+    Period,
+    Mass,
+    Drag,
+    Rolling,
+    MaxForce,
+}
+
+impl Field for VehicleField {
+    fn label(self) -> &'static str {
+        match self {
+            VehicleField::Profile => "road",
+            VehicleField::Grade => "grade",
+            VehicleField::Period => "period",
+            VehicleField::Mass => "m",
+            VehicleField::Drag => "CdA",
+            VehicleField::Rolling => "C_rr",
+            VehicleField::MaxForce => "F_max",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            VehicleField::Profile => FieldKind::Choice,
+            _ => FieldKind::Number,
+        }
+    }
+}
+
+impl VehicleSystem {
+    #[allow(non_snake_case)]
+    pub fn new(m: f64, CdA: f64, C_rr: f64, F_max: f64, Ts: f64) -> Self {
+        Self {
+            x: 0.0,
+            Ts,
+            profile: GradeProfile::Hills,
+            grade: 5.0,
+            period: 60.0,
+            m,
+            CdA,
+            C_rr,
+            F_max,
+            v: 0.0,
+            F: 0.0,
+            slope: 0.0,
+            edit: None,
+        }
+    }
+
+    /// Road grade in percent at the time `t`. The hills repeat a flat stretch, a climb, another
+    /// flat stretch and a descent, each a quarter of the period long.
+    fn grade_at(&self, t: f64) -> f64 {
+        let phase = if self.period > 0.0 {
+            (t / self.period).fract()
+        } else {
+            0.0
+        };
+        match self.profile {
+            GradeProfile::Flat => 0.0,
+            GradeProfile::Hills => match (phase * 4.0) as usize {
+                1 => self.grade,
+                3 => -self.grade,
+                _ => 0.0,
+            },
+            GradeProfile::Waves => self.grade * (2.0 * PI * phase).sin(),
+        }
+    }
+
+    fn acceleration(&self, v: f64, alpha: f64) -> f64 {
+        let (sin, cos) = alpha.sin_cos();
+        let drag = 0.5 * RHO * self.CdA * v * v;
+        (self.F * 1000.0 - drag - self.m * G * (self.C_rr * cos + sin)) / self.m
+    }
+
+    fn value(&self, field: VehicleField) -> f64 {
+        match field {
+            VehicleField::Profile => 0.0,
+            VehicleField::Grade => self.grade,
+            VehicleField::Period => self.period,
+            VehicleField::Mass => self.m,
+            VehicleField::Drag => self.CdA,
+            VehicleField::Rolling => self.C_rr,
+            VehicleField::MaxForce => self.F_max,
+        }
+    }
+}
+
+impl Default for VehicleSystem {
+    fn default() -> Self {
+        Self::new(1000.0, 0.6, 0.01, 4.0, 0.1)
+    }
+}
+
+impl FieldList for VehicleSystem {
+    type Field = VehicleField;
+
+    fn fields(&self) -> Vec<VehicleField> {
+        let mut fields = vec![VehicleField::Profile];
+        if self.profile != GradeProfile::Flat {
+            fields.extend([VehicleField::Grade, VehicleField::Period]);
+        }
+        fields.extend([
+            VehicleField::Mass,
+            VehicleField::Drag,
+            VehicleField::Rolling,
+            VehicleField::MaxForce,
+        ]);
+        fields
+    }
+
+    fn text(&self, field: VehicleField) -> String {
+        match field {
+            VehicleField::Profile => self.profile.label().to_string(),
+            _ => self.value(field).to_string(),
+        }
+    }
+
+    fn set_value(&mut self, field: VehicleField, value: f64) {
+        match field {
+            VehicleField::Profile => {}
+            VehicleField::Grade => self.grade = value,
+            VehicleField::Period => self.period = value.max(0.0),
+            // the mass divides the acceleration
+            VehicleField::Mass => {
+                if value > 0.0 {
+                    self.m = value;
+                }
+            }
+            VehicleField::Drag => self.CdA = value.max(0.0),
+            VehicleField::Rolling => self.C_rr = value.max(0.0),
+            VehicleField::MaxForce => self.F_max = value.max(0.0),
+        }
+    }
+
+    fn cycle(&mut self, _field: VehicleField, forward: bool) {
+        let idx = PROFILES
+            .iter()
+            .position(|p| *p == self.profile)
+            .unwrap_or(0);
+        self.profile = PROFILES[cycle_index(idx, PROFILES.len(), forward)];
+    }
+
+    fn field_edit(&self) -> Option<&(VehicleField, NumericInput)> {
+        self.edit.as_ref()
+    }
+
+    fn field_edit_mut(&mut self) -> &mut Option<(VehicleField, NumericInput)> {
+        &mut self.edit
+    }
+}
+
+impl Plant for VehicleSystem {
+    fn get_cursor_offsets(&self) -> (u16, u16) {
+        self.field_cursor(2)
+    }
+
+    fn edit(&mut self, editing: &mut Editing, k: crossterm::event::KeyEvent) {
+        self.edit_fields(editing, k);
+    }
+
+    fn set_input(&mut self, u: f64) {
+        self.F = u.clamp(0.0, self.F_max);
+    }
+
+    fn set_edit(&mut self) {
+        self.edit_first_field();
+    }
+
+    fn reset(&mut self) {
+        self.x = 0.0;
+        self.v = 0.0;
+        self.F = 0.0;
+        self.slope = 0.0;
+    }
+
+    fn render(&self, frame: &mut ratatui::Frame, area: Rect, state: &mut Editing) {
+        frame.render_stateful_widget_ref(self.clone(), area, state);
+    }
+    fn name(&self) -> &'static str {
+        PLANT_NAME
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        self.fields()
+            .iter()
+            .filter(|f| **f != VehicleField::Profile)
+            .map(|f| (f.label(), self.value(*f)))
+            .collect()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if let Some(field) = self.fields().into_iter().find(|f| f.label() == name) {
+            self.set_value(field, value);
+        }
+    }
+
+    fn plotted_state(&self) -> Option<(String, f64)> {
+        match self.profile {
+            GradeProfile::Flat => None,
+            _ => Some(("road grade %".to_string(), self.slope)),
+        }
+    }
+}
+
+impl Iterator for VehicleSystem {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        // the grade is held over the sampling period
+        self.slope = self.grade_at(self.x);
+        let alpha = (self.slope / 100.0).atan();
+        let h = self.Ts / SUB_STEPS as f64;
+        for _ in 0..SUB_STEPS {
+            let k1 = self.acceleration(self.v, alpha);
+            let k2 = self.acceleration(self.v + h / 2.0 * k1, alpha);
+            let k3 = self.acceleration(self.v + h / 2.0 * k2, alpha);
+            let k4 = self.acceleration(self.v + h * k3, alpha);
+            self.v = (self.v + h / 6.0 * (k1 + 2.0 * k2 + 2.0 * k3 + k4)).max(0.0);
+        }
+        let point = (self.x, self.v);
+        self.x += self.Ts;
+        Some(point)
+    }
+}
+
+impl StatefulWidgetRef for VehicleSystem {
+    type State = Editing;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let mut lines = vec![Line::from(Span::styled(
+            "Vehicle Cruise",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(self.field_lines());
+        lines.push(Line::from(Span::styled(
+            format!("F = {:.2} slope = {:.1}%", self.F, self.slope),
+            Style::default().gray().add_modifier(Modifier::BOLD),
+        )));
+        self.panel(lines).render(area, buf);
+    }
+}
+
+register_plant!(VehicleSystem, PLANT_NAME);
@@ -1,3 +1,12 @@
+use std::collections::VecDeque;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+
+use crate::Editing;
+
 #[derive(Default, Clone)]
 pub struct NumericInput {
     pub value: String, // text buffer
@@ -32,6 +41,39 @@ impl NumericInput {
     pub fn as_f64(&self) -> Option<f64> {
         self.value.parse().ok()
     }
+
+    /// Parses the buffer as a list of numbers separated by spaces, `None` if empty or malformed.
+    pub fn as_f64_list(&self) -> Option<Vec<f64>> {
+        let values: Option<Vec<f64>> = self
+            .value
+            .split_whitespace()
+            .map(|v| v.parse().ok())
+            .collect();
+        values.filter(|v| !v.is_empty())
+    }
+
+    /// Applies a text editing key (characters, deletion and cursor movement) to the buffer.
+    pub fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left if self.cursor > 0 => self.cursor -= 1,
+            KeyCode::Right if self.cursor < self.value.len() => self.cursor += 1,
+            _ => {}
+        }
+    }
+
+    /// Like `handle_key` for a list of numbers separated by spaces, e.g. polynomial coefficients.
+    pub fn handle_list_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(c) if c.is_ascii_digit() || matches!(c, '.' | '-' | ' ') => {
+                self.value.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            code => self.handle_key(code),
+        }
+    }
 }
 
 impl From<String> for NumericInput {
@@ -40,3 +82,346 @@ impl From<String> for NumericInput {
         NumericInput { value, cursor: len }
     }
 }
+
+/// Builds a `name = value` line of a settings panel.
+///
+/// If `input` is given the value is replaced by the edited text and highlighted. `editing` tells
+/// whether the whole panel is in edit mode, in which case the line is forced to white so it does
+/// not inherit the panel highlight color.
+pub fn param_line(name: &str, value: String, input: Option<&NumericInput>, editing: bool) -> Line<'static> {
+    let line = match input {
+        Some(input) => Line::from(vec![
+            Span::raw(format!("{} = ", name)).white(),
+            Span::styled(input.value.clone(), Style::default().cyan()),
+        ]),
+        None if editing => Line::from(Span::raw(format!("{} = {}", name, value))).white(),
+        None => Line::from(Span::raw(format!("{} = {}", name, value))),
+    };
+    line.add_modifier(Modifier::BOLD)
+}
+
+/// Returns the index following (or preceding) `idx` in a list of `len` items, wrapping around.
+pub fn cycle_index(idx: usize, len: usize, forward: bool) -> usize {
+    if forward {
+        (idx + 1) % len
+    } else {
+        (idx + len - 1) % len
+    }
+}
+
+/// Fixed length FIFO delaying a sampled signal by a whole number of samples.
+#[derive(Clone, Default)]
+pub struct DelayLine {
+    buffer: VecDeque<f64>,
+}
+
+impl DelayLine {
+    /// Longest delay in samples, longer lines are cut to it.
+    pub const MAX_SAMPLES: usize = 1000;
+
+    pub fn new(samples: usize) -> Self {
+        Self {
+            buffer: VecDeque::from(vec![0.0; samples.min(Self::MAX_SAMPLES)]),
+        }
+    }
+
+    /// Number of samples needed to delay a signal sampled every `ts` by `delay` seconds, at most
+    /// `MAX_SAMPLES`.
+    pub fn samples_for(delay: f64, ts: f64) -> usize {
+        ((delay.max(0.0) / ts).round() as usize).min(Self::MAX_SAMPLES)
+    }
+
+    /// Pushes a new sample and returns the one delayed by the length of the line.
+    pub fn push(&mut self, value: f64) -> f64 {
+        self.buffer.push_back(value);
+        self.buffer.pop_front().unwrap_or(value)
+    }
+
+    /// Changes the delay length, keeping the most recent samples.
+    pub fn resize(&mut self, samples: usize) {
+        let samples = samples.min(Self::MAX_SAMPLES);
+        while self.buffer.len() > samples {
+            self.buffer.pop_front();
+        }
+        while self.buffer.len() < samples {
+            self.buffer.push_front(self.buffer.front().copied().unwrap_or(0.0));
+        }
+    }
+
+    /// Samples waiting in the line, from the most recent to the next one to come out.
+    pub fn newest_first(&self) -> impl Iterator<Item = f64> + '_ {
+        self.buffer.iter().rev().copied()
+    }
+
+    pub fn reset(&mut self) {
+        self.fill(0.0);
+    }
+
+    /// Fills the line with `value`, as if the signal had been constant for the whole delay.
+    pub fn fill(&mut self, value: f64) {
+        self.buffer.iter_mut().for_each(|v| *v = value);
+    }
+}
+
+/// Builds a `name = <value>` line of a settings panel for a value chosen from a list of options.
+///
+/// `selected` highlights the value while it is cycled with the Left/Right keys.
+pub fn choice_line(name: &str, value: &str, selected: bool, editing: bool) -> Line<'static> {
+    if selected {
+        Line::from(vec![
+            Span::raw(format!("{} = ", name)).white(),
+            Span::styled(format!("<{}>", value), Style::default().cyan()),
+        ])
+        .add_modifier(Modifier::BOLD)
+    } else {
+        param_line(name, value.to_string(), None, editing)
+    }
+}
+
+/// How the value of a settings field is entered.
+#[derive(Clone, Copy, PartialEq)]
+pub enum FieldKind {
+    /// Typed number.
+    Number,
+    /// Typed list of numbers separated by spaces.
+    List,
+    /// Option cycled with the Left/Right keys.
+    Choice,
+}
+
+/// Field of a settings panel.
+pub trait Field: Copy + PartialEq {
+    fn label(self) -> &'static str;
+    fn kind(self) -> FieldKind {
+        FieldKind::Number
+    }
+}
+
+/// Settings panel made of `name = value` lines edited one field at a time.
+///
+/// Up/Down commit the edited field and move to the next or previous one, Enter commits it and
+/// closes the panel, Esc closes it dropping the edit and Left/Right cycle the options of choice
+/// fields. Implementors describe their fields, the provided methods do the editing and render
+/// the field lines.
+pub trait FieldList {
+    type Field: Field;
+
+    /// Fields shown in the panel, in display order.
+    fn fields(&self) -> Vec<Self::Field>;
+    /// Value shown for `field`, also the initial text when it is edited.
+    fn text(&self, field: Self::Field) -> String;
+    fn set_value(&mut self, field: Self::Field, value: f64);
+    /// Writes the edited text of a number or list field back.
+    fn commit(&mut self, field: Self::Field, input: &NumericInput) {
+        if let Some(num) = input.as_f64() {
+            self.set_value(field, num);
+        }
+    }
+    /// Selects the next or previous option of a choice field.
+    fn cycle(&mut self, _field: Self::Field, _forward: bool) {}
+
+    /// Edited field and its text buffer.
+    fn field_edit(&self) -> Option<&(Self::Field, NumericInput)>;
+    fn field_edit_mut(&mut self) -> &mut Option<(Self::Field, NumericInput)>;
+
+    fn field_input(&self, field: Self::Field) -> (Self::Field, NumericInput) {
+        let text = match field.kind() {
+            FieldKind::Choice => String::new(),
+            _ => self.text(field),
+        };
+        (field, NumericInput::from(text))
+    }
+
+    /// Starts editing the first field.
+    fn edit_first_field(&mut self) {
+        let edit = self.fields().first().map(|f| self.field_input(*f));
+        *self.field_edit_mut() = edit;
+    }
+
+    fn edit_fields(&mut self, editing: &mut Editing, k: KeyEvent) {
+        if self.field_edit().is_none() {
+            self.edit_first_field();
+        }
+        let Some((field, input)) = self.field_edit_mut().as_mut() else {
+            return;
+        };
+        let field = *field;
+
+        match k.code {
+            KeyCode::Esc => {
+                *editing = Editing::None;
+                *self.field_edit_mut() = None;
+            }
+            KeyCode::Left | KeyCode::Right if field.kind() == FieldKind::Choice => {
+                self.cycle(field, k.code == KeyCode::Right);
+            }
+            KeyCode::Down | KeyCode::Up | KeyCode::Enter => {
+                if field.kind() != FieldKind::Choice {
+                    let input = input.clone();
+                    self.commit(field, &input);
+                }
+                // committing may change the fields, e.g. hide the ones of a disabled stage
+                let fields = self.fields();
+                let idx = fields.iter().position(|f| *f == field).unwrap_or(0);
+                let edit = match k.code {
+                    KeyCode::Enter => {
+                        *editing = Editing::None;
+                        None
+                    }
+                    code => {
+                        let next = cycle_index(idx, fields.len(), code == KeyCode::Down);
+                        Some(self.field_input(fields[next]))
+                    }
+                };
+                *self.field_edit_mut() = edit;
+            }
+            code => match field.kind() {
+                FieldKind::Number => input.handle_key(code),
+                FieldKind::List => input.handle_list_key(code),
+                FieldKind::Choice => {}
+            },
+        }
+    }
+
+    /// Cursor position in the panel, `first_row` being the row of the first field.
+    fn field_cursor(&self, first_row: u16) -> (u16, u16) {
+        match self.field_edit() {
+            Some((field, input)) => {
+                let row = self.fields().iter().position(|f| f == field).unwrap_or(0);
+                let cursor = match field.kind() {
+                    FieldKind::Choice => 0,
+                    _ => input.cursor,
+                };
+                (
+                    field.label().len() as u16 + 4 + cursor as u16,
+                    row as u16 + first_row,
+                )
+            }
+            None => (0, 0),
+        }
+    }
+
+    /// Line of `field` in the panel.
+    fn field_line(&self, field: Self::Field) -> Line<'static> {
+        let editing = self.field_edit().is_some();
+        let input = self
+            .field_edit()
+            .filter(|(f, _)| *f == field)
+            .map(|(_, input)| input);
+        match field.kind() {
+            FieldKind::Choice => {
+                choice_line(field.label(), &self.text(field), input.is_some(), editing)
+            }
+            _ => param_line(field.label(), self.text(field), input, editing),
+        }
+    }
+
+    fn field_lines(&self) -> Vec<Line<'static>> {
+        self.fields()
+            .into_iter()
+            .map(|f| self.field_line(f))
+            .collect()
+    }
+
+    /// Panel showing `lines`, highlighted while a field is edited.
+    fn panel(&self, lines: Vec<Line<'static>>) -> Paragraph<'static> {
+        if self.field_edit().is_some() {
+            Paragraph::new(lines).add_modifier(Modifier::BOLD)
+        } else {
+            Paragraph::new(lines)
+        }
+    }
+}
+
+/// First order filter K (T_lead s + 1) / (T_lag s + 1) discretized with the bilinear (Tustin)
+/// transform.
+///
+/// A pure lead is not realizable, so the lag is kept at least one sampling period long whenever a
+/// lead is requested.
+#[derive(Clone, Default)]
+pub struct LeadLag {
+    b: (f64, f64), // b0, b1
+    a1: f64,
+    u1: f64, // previous input
+    y1: f64, // previous output
+}
+
+impl LeadLag {
+    pub fn new(k: f64, t_lead: f64, t_lag: f64, ts: f64) -> Self {
+        let t_lead = t_lead.max(0.0);
+        let t_lag = if t_lead > 0.0 { t_lag.max(ts) } else { t_lag.max(0.0) };
+        if t_lag == 0.0 {
+            // static gain, avoids the pole-zero pair at z = -1 of the bilinear transform
+            return Self {
+                b: (k, 0.0),
+                ..Self::default()
+            };
+        }
+        let c = 2.0 / ts;
+        let a0 = 1.0 + c * t_lag;
+        Self {
+            b: (k * (1.0 + c * t_lead) / a0, k * (1.0 - c * t_lead) / a0),
+            a1: (1.0 - c * t_lag) / a0,
+            u1: 0.0,
+            y1: 0.0,
+        }
+    }
+
+    /// Filters the next input sample.
+    pub fn step(&mut self, u: f64) -> f64 {
+        let y = self.b.0 * u + self.b.1 * self.u1 - self.a1 * self.y1;
+        self.u1 = u;
+        self.y1 = y;
+        y
+    }
+
+    /// Replaces the coefficients while keeping the filter state.
+    pub fn retune(&mut self, k: f64, t_lead: f64, t_lag: f64, ts: f64) {
+        let state = (self.u1, self.y1);
+        *self = Self::new(k, t_lead, t_lag, ts);
+        (self.u1, self.y1) = state;
+    }
+
+    pub fn reset(&mut self) {
+        self.u1 = 0.0;
+        self.y1 = 0.0;
+    }
+}
+
+/// Small seeded pseudo random generator (SplitMix64), so that noisy simulations can be replayed
+/// exactly from the same seed.
+#[derive(Clone, Default)]
+pub struct Rng {
+    state: u64,
+    spare: Option<f64>, // second normal sample of the last Box-Muller draw
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed, spare: None }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform sample in (0, 1].
+    pub fn uniform(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal sample, drawn in pairs with the Box-Muller transform.
+    pub fn gaussian(&mut self) -> f64 {
+        if let Some(z) = self.spare.take() {
+            return z;
+        }
+        let r = (-2.0 * self.uniform().ln()).sqrt();
+        let (sin, cos) = (2.0 * std::f64::consts::PI * self.uniform()).sin_cos();
+        self.spare = Some(r * sin);
+        r * cos
+    }
+}